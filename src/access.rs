@@ -0,0 +1,23 @@
+//! Whether the current user can actually enter a directory: entries left
+//! behind by `sudo` or another account, or whose permissions changed, still
+//! match but `cd` would fail on them.
+
+use std::path::Path;
+
+/// Whether `path` has the execute permission `cd` needs, for the real user.
+/// Only the permission is checked, a missing directory is enterable as far
+/// as this is concerned.
+#[cfg(unix)]
+pub fn can_enter(path: &Path) -> bool {
+    use rustix::fs::{access, Access};
+    use rustix::io::Errno;
+    match access(path, Access::EXEC_OK) {
+        Ok(()) | Err(Errno::NOENT) => true,
+        Err(_) => false,
+    }
+}
+
+#[cfg(not(unix))]
+pub fn can_enter(_path: &Path) -> bool {
+    true
+}
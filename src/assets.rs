@@ -0,0 +1,52 @@
+//! Files for packagers to install at build time instead of having users
+//! eval `wdbin init` at runtime: the init scripts, shell completions and the
+//! man page.
+
+use crate::init::Shell;
+use crate::messages;
+use clap::ValueEnum;
+use eyre::Context;
+use std::path::{Path, PathBuf};
+
+fn write(path: PathBuf, content: &[u8]) -> eyre::Result<PathBuf> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).wrap_err_with(|| messages::couldnt_create(dir))?;
+    }
+    std::fs::write(&path, content).wrap_err_with(|| messages::error_writing(&path))?;
+    Ok(path)
+}
+
+/// Write everything under `out`, as `init/wd.<shell>`, `completions/` in
+/// each shell's naming convention and `man/wdbin.1`. Returns the written
+/// files.
+pub fn generate(mut cmd: clap::Command, out: &Path) -> eyre::Result<Vec<PathBuf>> {
+    let mut written = vec![];
+    for shell in Shell::value_variants() {
+        let name = shell
+            .to_possible_value()
+            .map_or_else(String::new, |v| v.get_name().to_string());
+        written.push(write(
+            out.join("init").join(format!("wd.{name}")),
+            shell.script().as_bytes(),
+        )?);
+    }
+
+    let bin = cmd.get_name().to_string();
+    let completions = [
+        (clap_complete::Shell::Bash, format!("{bin}.bash")),
+        (clap_complete::Shell::Zsh, format!("_{bin}")),
+        (clap_complete::Shell::Fish, format!("{bin}.fish")),
+    ];
+    for (shell, file) in completions {
+        let mut script = vec![];
+        clap_complete::generate(shell, &mut cmd, &bin, &mut script);
+        written.push(write(out.join("completions").join(file), &script)?);
+    }
+
+    let mut page = vec![];
+    clap_mangen::Man::new(cmd)
+        .render(&mut page)
+        .wrap_err_with(messages::couldnt_render_man)?;
+    written.push(write(out.join("man").join(format!("{bin}.1")), &page)?);
+    Ok(written)
+}
@@ -0,0 +1,61 @@
+use crate::matcher::rank;
+use crate::store::Entry;
+use std::path::PathBuf;
+use std::time::Instant;
+
+const WORDS: &[&str] = &[
+    "dev", "src", "project", "work", "api", "frontend", "backend", "docs", "infra", "tools",
+    "client", "server", "lib", "config", "scripts", "tests", "assets", "notes", "build", "deploy",
+];
+
+/// Deterministic pseudo random paths, 2 to 6 components deep under a fake home.
+fn synthetic_entries(size: usize) -> Vec<Entry> {
+    let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut next = move || {
+        // xorshift64
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        seed as usize
+    };
+
+    (0..size)
+        .map(|i| {
+            let mut path = PathBuf::from("/home/user");
+            for _ in 0..2 + next() % 5 {
+                path.push(WORDS[next() % WORDS.len()]);
+            }
+            path.push(format!("{}-{i}", WORDS[next() % WORDS.len()]));
+            Entry {
+                path,
+                last_access: 0,
+                visits: 0,
+                hosts: vec![],
+                id: None,
+            }
+        })
+        .collect()
+}
+
+pub fn run(input: &str, size: usize, iterations: usize, min_confidence: f64) -> eyre::Result<()> {
+    let entries = synthetic_entries(size);
+
+    let mut timings = Vec::with_capacity(iterations);
+    let mut matches = 0;
+    for _ in 0..iterations.max(1) {
+        let now = Instant::now();
+        matches = rank(&entries, input, min_confidence, &[])?.len();
+        timings.push(now.elapsed().as_micros() as f64 / 1000.);
+    }
+    timings.sort_by(f64::total_cmp);
+
+    let mean = timings.iter().sum::<f64>() / timings.len() as f64;
+    println!("{size} entries, {matches} matches for {input:?}");
+    println!(
+        "min: {:.2} ms, median: {:.2} ms, mean: {mean:.2} ms, max: {:.2} ms",
+        timings[0],
+        timings[timings.len() / 2],
+        timings[timings.len() - 1]
+    );
+    Ok(())
+}
@@ -0,0 +1,159 @@
+//! Remembering the matches of recent queries, since completion integrations
+//! run wd on every keystroke and the same prefixes come back over and over.
+//! Anything written to the db or the config makes the whole cache stale.
+
+use crate::messages;
+use crate::sources;
+use crate::store::{self, now};
+use crate::{CompleteResult, Origin};
+use eyre::Context;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// How many queries are remembered.
+const CAPACITY: usize = 32;
+/// Seconds matches are trusted for, since sources like `scan` look at the
+/// filesystem rather than the db.
+const TTL: u64 = 60;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Cached {
+    key: String,
+    at: u64,
+    /// confidence, path and source name of each match
+    matches: Vec<(f64, PathBuf, String)>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Content {
+    fingerprint: u64,
+    /// most recently used first
+    queries: Vec<Cached>,
+}
+
+#[derive(Debug, Default)]
+pub struct QueryCache {
+    /// `None` when caching is disabled
+    file_path: Option<PathBuf>,
+    content: Content,
+    dirty: bool,
+}
+
+/// Changes whenever one of `files` is written to, or `extra` changes.
+pub fn fingerprint(files: &[&Path], extra: &str) -> u64 {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    let mut hasher = DefaultHasher::new();
+    for file in files {
+        let metadata = std::fs::metadata(file).ok();
+        metadata
+            .as_ref()
+            .map(std::fs::Metadata::len)
+            .hash(&mut hasher);
+        metadata
+            .and_then(|m| m.modified().ok())
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .hash(&mut hasher);
+    }
+    extra.hash(&mut hasher);
+    hasher.finish()
+}
+
+impl QueryCache {
+    /// A cache that never remembers anything.
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    /// Open the cache at `file_path`, dropping it if it was filled with a
+    /// different `fingerprint`.
+    pub fn open(file_path: PathBuf, fingerprint: u64) -> eyre::Result<Self> {
+        let content = match std::fs::read(&file_path) {
+            Ok(raw) => serde_json::from_slice(&raw)
+                .wrap_err_with(|| messages::error_loading(&file_path))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Content::default(),
+            Err(e) => return Err(e).wrap_err_with(|| messages::error_loading(&file_path)),
+        };
+        let stale = content.fingerprint != fingerprint;
+        Ok(Self {
+            file_path: Some(file_path),
+            content: if stale {
+                Content {
+                    fingerprint,
+                    queries: vec![],
+                }
+            } else {
+                content
+            },
+            dirty: stale,
+        })
+    }
+
+    /// Matches remembered for `key`, unless they're too old or a source
+    /// isn't known anymore.
+    pub fn get(&mut self, key: &str) -> Option<Vec<CompleteResult>> {
+        self.file_path.as_ref()?;
+        let now = now();
+        let i = self
+            .content
+            .queries
+            .iter()
+            .position(|q| q.key == key && now.saturating_sub(q.at) < TTL)?;
+        let matches = self.content.queries[i]
+            .matches
+            .iter()
+            .map(|(confidence, path, source)| {
+                Some(CompleteResult::new(
+                    *confidence,
+                    path.clone(),
+                    Origin::Source(sources::static_name(source)?),
+                ))
+            })
+            .collect::<Option<_>>()?;
+        if i > 0 {
+            let cached = self.content.queries.remove(i);
+            self.content.queries.insert(0, cached);
+            self.dirty = true;
+        }
+        Some(matches)
+    }
+
+    pub fn put(&mut self, key: String, matches: &[CompleteResult]) {
+        if self.file_path.is_none() {
+            return;
+        }
+        let matches = matches
+            .iter()
+            .filter_map(|m| match m.source {
+                Origin::Source(name) => Some((m.confidence, m.path.clone(), name.to_string())),
+                Origin::Filesystem => None,
+            })
+            .collect();
+        self.content.queries.retain(|q| q.key != key);
+        self.content.queries.insert(
+            0,
+            Cached {
+                key,
+                at: now(),
+                matches,
+            },
+        );
+        self.content.queries.truncate(CAPACITY);
+        self.dirty = true;
+    }
+
+    pub fn write(&mut self) -> eyre::Result<()> {
+        let Some(file_path) = &self.file_path else {
+            return Ok(());
+        };
+        if !self.dirty {
+            return Ok(());
+        }
+        store::write_private(file_path, &serde_json::to_vec(&self.content)?)
+            .wrap_err_with(|| messages::error_writing(file_path))?;
+        self.dirty = false;
+        Ok(())
+    }
+}
@@ -0,0 +1,94 @@
+//! Putting text on the system clipboard, through whichever tool the session
+//! has, or the terminal itself with an OSC 52 escape sequence when there's
+//! none or wd runs over ssh.
+
+use crate::messages;
+use eyre::{bail, Context};
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = (u32::from(b[0]) << 16) | (u32::from(b[1]) << 8) | u32::from(b[2]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(BASE64[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Clipboard tools to try, in order, for this session.
+fn tools() -> Vec<&'static [&'static str]> {
+    let set = |var| std::env::var_os(var).is_some_and(|v| !v.is_empty());
+    // a clipboard tool over ssh would copy on the remote machine
+    if set("SSH_TTY") || set("SSH_CONNECTION") {
+        return vec![];
+    }
+    let mut tools: Vec<&'static [&'static str]> = vec![];
+    if cfg!(target_os = "macos") {
+        tools.push(&["pbcopy"]);
+    }
+    if set("WAYLAND_DISPLAY") {
+        tools.push(&["wl-copy"]);
+    }
+    if set("DISPLAY") {
+        tools.push(&["xclip", "-selection", "clipboard"]);
+        tools.push(&["xsel", "--clipboard", "--input"]);
+    }
+    tools
+}
+
+fn pipe_to(tool: &[&str], text: &str) -> eyre::Result<()> {
+    let mut child = Command::new(tool[0])
+        .args(&tool[1..])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    child
+        .stdin
+        .take()
+        .expect("stdin is piped")
+        .write_all(text.as_bytes())?;
+    let status = child.wait()?;
+    if !status.success() {
+        bail!(messages::command_failed(tool[0], status));
+    }
+    Ok(())
+}
+
+/// Ask the terminal to set the clipboard, passing through tmux if needed.
+fn osc52(text: &str) -> eyre::Result<()> {
+    let mut sequence = format!("\x1b]52;c;{}\x07", base64(text.as_bytes()));
+    if std::env::var_os("TMUX").is_some() {
+        sequence = format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b"));
+    }
+    // stdout may be captured by the shell, talk to the terminal directly
+    let mut tty = std::fs::OpenOptions::new()
+        .write(true)
+        .open("/dev/tty")
+        .wrap_err_with(messages::couldnt_open_terminal)?;
+    tty.write_all(sequence.as_bytes())?;
+    Ok(())
+}
+
+pub fn copy(text: &str) -> eyre::Result<()> {
+    for tool in tools() {
+        if pipe_to(tool, text).is_ok() {
+            return Ok(());
+        }
+    }
+    osc52(text).wrap_err_with(messages::no_clipboard)
+}
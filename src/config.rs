@@ -0,0 +1,449 @@
+use crate::messages;
+use crate::translit::Stage;
+use dirs::config_dir;
+use eyre::{bail, eyre, Context};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum StoreKind {
+    /// a single json file, rewritten on every change
+    #[default]
+    Json,
+    /// an sqlite database, for big histories and concurrent shells
+    Sqlite,
+    /// read the json database but never write anything
+    Memory,
+}
+
+/// What to do with matches the user isn't allowed to enter, see `access.rs`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Inaccessible {
+    /// leave them out
+    #[default]
+    Hide,
+    /// keep them, flagged in listings and menus
+    Mark,
+}
+
+/// User configuration, read from `$XDG_CONFIG_HOME/wd/config.toml`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub store: StoreKind,
+    pub db_path: Option<PathBuf>,
+    /// default minimum confidence for a match
+    pub confidence: Option<f64>,
+    /// confidence above which `wd` doesn't offer a choice
+    pub sure_confidence: Option<f64>,
+    /// (query length, scale) points scaling fuzzy scores by query length, a
+    /// built-in curve if unset and none if empty, see `matcher.rs`
+    pub calibration: Option<Vec<(usize, f64)>>,
+    /// matches the user can't `cd` into
+    pub inaccessible: Inaccessible,
+    /// seconds during which a directory's existence is trusted without
+    /// checking it again
+    pub existence_cache_ttl: Option<u64>,
+    pub open: OpenConfig,
+    pub sources: SourcesConfig,
+    pub projects: ProjectsConfig,
+    /// rewrites applied to paths and queries before matching, see
+    /// `translit.rs`
+    pub transliterate: Vec<Stage>,
+    pub hosts: HostsConfig,
+    pub fusion: FusionConfig,
+    pub temporal: TemporalConfig,
+    pub exclude: ExcludeConfig,
+    /// load the database even if other users can write to it
+    pub allow_shared_db: bool,
+    /// directories never recorded nor listed, with everything under them,
+    /// see `private.rs`
+    pub private_paths: Vec<PathBuf>,
+    /// shell code the `wd` function runs after a jump, `{path}` being the
+    /// new directory, see `hooks.rs`
+    pub on_jump: Option<String>,
+    /// profile in use, its `[profiles.<name>]` table overriding the rest of
+    /// the config
+    #[serde(skip)]
+    pub profile: Option<String>,
+}
+
+/// Where candidates come from, see `sources.rs`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SourcesConfig {
+    pub history: HistoryConfig,
+    pub bookmarks: BookmarksConfig,
+    pub scan: ScanConfig,
+    pub repos: ReposConfig,
+    pub worktrees: WorktreesConfig,
+}
+
+const fn yes() -> bool {
+    true
+}
+
+const fn full_weight() -> f64 {
+    1.
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HistoryConfig {
+    #[serde(default = "yes")]
+    pub enabled: bool,
+    #[serde(default = "full_weight")]
+    pub weight: f64,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            weight: 1.,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BookmarksConfig {
+    #[serde(default = "yes")]
+    pub enabled: bool,
+    #[serde(default = "full_weight")]
+    pub weight: f64,
+    #[serde(default)]
+    pub paths: Vec<PathBuf>,
+}
+
+impl Default for BookmarksConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            weight: 1.,
+            paths: vec![],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScanConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "ScanConfig::default_weight")]
+    pub weight: f64,
+    /// directories to scan, the one the jump starts from if empty
+    #[serde(default)]
+    pub roots: Vec<PathBuf>,
+    #[serde(default = "ScanConfig::default_depth")]
+    pub depth: usize,
+}
+
+impl ScanConfig {
+    const fn default_weight() -> f64 {
+        0.8
+    }
+
+    const fn default_depth() -> usize {
+        1
+    }
+}
+
+impl Default for ScanConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            weight: Self::default_weight(),
+            roots: vec![],
+            depth: Self::default_depth(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ReposConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "ReposConfig::default_weight")]
+    pub weight: f64,
+    /// directories to look for repositories in
+    #[serde(default)]
+    pub roots: Vec<PathBuf>,
+    #[serde(default = "ReposConfig::default_depth")]
+    pub depth: usize,
+}
+
+impl ReposConfig {
+    const fn default_weight() -> f64 {
+        0.9
+    }
+
+    const fn default_depth() -> usize {
+        3
+    }
+}
+
+impl Default for ReposConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            weight: Self::default_weight(),
+            roots: vec![],
+            depth: Self::default_depth(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WorktreesConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "WorktreesConfig::default_weight")]
+    pub weight: f64,
+}
+
+impl WorktreesConfig {
+    const fn default_weight() -> f64 {
+        0.9
+    }
+}
+
+impl Default for WorktreesConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            weight: Self::default_weight(),
+        }
+    }
+}
+
+/// Ranking candidates by several matching strategies at once, see
+/// `matcher::rank_fused`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FusionConfig {
+    pub enabled: bool,
+    /// reciprocal rank fusion constant, higher values flatten the
+    /// difference between ranks
+    pub k: f64,
+    /// weight of each strategy, 0 leaving it out
+    pub edit: f64,
+    pub subsequence: f64,
+    pub prefix: f64,
+    pub acronym: f64,
+}
+
+impl Default for FusionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            k: 60.,
+            edit: 1.,
+            subsequence: 1.,
+            prefix: 1.,
+            acronym: 1.,
+        }
+    }
+}
+
+/// Directories left out of matches even when they score well, on top of
+/// `complete`'s `--exclude*` flags.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExcludeConfig {
+    /// the directory the jump starts from
+    pub cwd: bool,
+    /// the parents of the directory the jump starts from
+    pub ancestors: bool,
+    pub paths: Vec<PathBuf>,
+}
+
+/// Boosting directories usually visited at this time of the day and week,
+/// see `temporal.rs`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TemporalConfig {
+    pub enabled: bool,
+    /// largest boost, for a directory only ever visited at this time
+    pub boost: f64,
+    /// timezone of the visits, like `+02:00`, the system's if unset
+    pub utc_offset: Option<String>,
+}
+
+impl Default for TemporalConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            boost: 0.15,
+            utc_offset: None,
+        }
+    }
+}
+
+/// Programs `wdbin open` can launch.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OpenConfig {
+    /// program used without `--with`, `editor` if unset
+    pub default: Option<String>,
+    /// command templates by name, `{path}` being the matched directory
+    pub programs: HashMap<String, String>,
+}
+
+/// Sharing one synced database between machines.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HostsConfig {
+    /// tag entries with the machines they're visited on, and leave entries
+    /// only visited on other machines out of matches
+    pub tag: bool,
+    /// name of this machine, its hostname if unset
+    pub name: Option<String>,
+}
+
+/// What `wdbin projects --scan` considers a project.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProjectsConfig {
+    /// files or directories marking a project root, a built-in list if unset
+    pub markers: Option<Vec<String>>,
+}
+
+impl Config {
+    pub fn default_path() -> Option<PathBuf> {
+        config_dir().map(|mut p| {
+            p.push("wd/config.toml");
+            p
+        })
+    }
+
+    /// Path of the config file in use, if there is one.
+    pub fn path(path: Option<&Path>) -> Option<PathBuf> {
+        path.map(Path::to_path_buf).or_else(Self::default_path)
+    }
+
+    fn read_table(path: &Path) -> eyre::Result<Option<toml::Table>> {
+        match std::fs::read_to_string(path) {
+            Ok(raw) => Ok(Some(toml::from_str(&raw).wrap_err_with(|| {
+                messages::config_parse(&path.display().to_string())
+            })?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e).wrap_err_with(|| messages::config_read(path)),
+        }
+    }
+
+    /// Set a single top level `key` in the config file at `path`, or in the
+    /// table of `profile` if given, creating it if needed. Comments in the
+    /// file are not preserved.
+    pub fn set(
+        path: &Path,
+        profile: Option<&str>,
+        key: &str,
+        value: impl Into<toml::Value>,
+    ) -> eyre::Result<()> {
+        let mut root = Self::read_table(path)?.unwrap_or_default();
+        let mut table = &mut root;
+        if let Some(profile) = profile {
+            for name in ["profiles", profile] {
+                table = table
+                    .entry(name)
+                    .or_insert_with(|| toml::Table::new().into())
+                    .as_table_mut()
+                    .ok_or_else(|| eyre!(messages::config_not_table(name, path)))?;
+            }
+        }
+        table.insert(key.to_string(), value.into());
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, toml::to_string(&root)?).wrap_err_with(|| messages::config_write(path))
+    }
+
+    /// Add `value` to the top level list `key` in the config file at `path`
+    /// if it isn't there yet. Returns whether it was added.
+    pub fn append(path: &Path, key: &str, value: &str) -> eyre::Result<bool> {
+        let root = Self::read_table(path)?.unwrap_or_default();
+        let mut list = match root.get(key) {
+            Some(toml::Value::Array(list)) => list.clone(),
+            Some(_) => bail!(messages::config_not_list(key, path)),
+            None => vec![],
+        };
+        if list.iter().any(|v| v.as_str() == Some(value)) {
+            return Ok(false);
+        }
+        list.push(value.into());
+        Self::set(path, None, key, list)?;
+        Ok(true)
+    }
+
+    /// Load the config at `path`, falling back to the default location, with
+    /// the overrides of `profile` applied. A missing file is an empty config.
+    pub fn load(path: Option<&Path>, profile: Option<&str>) -> eyre::Result<Self> {
+        if let Some(profile) = profile {
+            let valid = !profile.is_empty()
+                && profile
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+            if !valid {
+                bail!(messages::bad_profile(profile));
+            }
+        }
+        let path = Self::path(path);
+        let mut table = match &path {
+            Some(path) => Self::read_table(path)?.unwrap_or_default(),
+            None => toml::Table::new(),
+        };
+
+        let profiles = table.remove("profiles");
+        let overrides = profile.and_then(|profile| {
+            profiles
+                .as_ref()
+                .and_then(|p| p.get(profile))
+                .and_then(toml::Value::as_table)
+        });
+        if let Some(overrides) = overrides {
+            merge(&mut table, overrides.clone());
+        }
+
+        let name = path
+            .as_ref()
+            .map_or_else(String::new, |path| path.display().to_string());
+        let mut config: Self = table
+            .try_into()
+            .wrap_err_with(|| messages::config_parse(&name))?;
+        if let Some(curve) = &config.calibration {
+            let increasing = curve.windows(2).all(|w| w[0].0 < w[1].0);
+            if !increasing || curve.iter().any(|&(_, scale)| scale <= 0.) {
+                bail!(messages::bad_calibration(&name));
+            }
+        }
+        if !(0. ..1.).contains(&config.temporal.boost) {
+            bail!(messages::bad_boost(&name));
+        }
+        if let Some(offset) = &config.temporal.utc_offset {
+            if crate::temporal::parse_offset(offset).is_none() {
+                bail!(messages::bad_utc_offset(offset, &name));
+            }
+        }
+        config.profile = profile.map(str::to_string);
+        Ok(config)
+    }
+}
+
+/// Merge `overrides` into `table`, recursing into tables present in both.
+fn merge(table: &mut toml::Table, overrides: toml::Table) {
+    for (key, value) in overrides {
+        match (table.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(value)) => merge(base, value),
+            (_, value) => {
+                table.insert(key, value);
+            }
+        }
+    }
+}
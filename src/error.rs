@@ -0,0 +1,100 @@
+//! Failures worth telling apart, each with its exit code and message. Other
+//! errors stay plain `eyre` reports and exit with `EXIT_ERROR`.
+
+use crate::messages;
+use crate::{EXIT_AMBIGUOUS, EXIT_ERROR, EXIT_NO_MATCH};
+use std::fmt;
+use std::path::PathBuf;
+
+#[derive(Debug)]
+pub enum WdError {
+    /// the database can't be parsed
+    DbCorrupt {
+        path: PathBuf,
+        reason: String,
+    },
+    /// another process holds the database for too long
+    #[cfg_attr(not(feature = "sqlite"), allow(dead_code))]
+    DbLocked {
+        path: PathBuf,
+    },
+    /// other users can write to the database
+    DbInsecure {
+        path: PathBuf,
+    },
+    NoMatch {
+        query: String,
+    },
+    /// several candidates and none sure enough, for the user to pick one
+    Ambiguous {
+        query: String,
+        count: usize,
+    },
+    /// wd is in read-only mode and was asked to write
+    ReadOnly,
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+}
+
+impl WdError {
+    pub const fn exit_code(&self) -> i32 {
+        match self {
+            Self::NoMatch { .. } => EXIT_NO_MATCH,
+            Self::Ambiguous { .. } => EXIT_AMBIGUOUS,
+            Self::DbCorrupt { .. }
+            | Self::DbLocked { .. }
+            | Self::DbInsecure { .. }
+            | Self::ReadOnly
+            | Self::Io { .. } => EXIT_ERROR,
+        }
+    }
+
+    /// Whether this is an expected outcome rather than something going
+    /// wrong, to be reported without the error's details.
+    pub const fn is_outcome(&self) -> bool {
+        matches!(self, Self::NoMatch { .. } | Self::Ambiguous { .. })
+    }
+}
+
+impl fmt::Display for WdError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let message = match self {
+            Self::DbCorrupt { path, reason } => messages::db_corrupt(path, reason),
+            Self::DbLocked { path } => messages::db_locked(path),
+            Self::DbInsecure { path } => messages::db_insecure(path),
+            Self::NoMatch { query } => messages::no_match(query),
+            Self::Ambiguous { query, count } => messages::ambiguous(query, *count),
+            Self::ReadOnly => messages::read_only(),
+            Self::Io { path, .. } => messages::io(path),
+        };
+        f.write_str(&message)
+    }
+}
+
+impl std::error::Error for WdError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl WdError {
+    /// Classify a failure to load the database at `path`.
+    pub fn loading(path: PathBuf, err: std::io::Error) -> Self {
+        if matches!(
+            err.kind(),
+            std::io::ErrorKind::InvalidData | std::io::ErrorKind::UnexpectedEof
+        ) {
+            Self::DbCorrupt {
+                path,
+                reason: err.to_string(),
+            }
+        } else {
+            Self::Io { path, source: err }
+        }
+    }
+}
@@ -0,0 +1,121 @@
+//! Output of `wdbin __jump`, the command behind the shell functions: shell
+//! code for the function to eval as is, one step per line. New things to do
+//! on a jump are new steps rendered here, the shell functions don't change.
+//!
+//! Exit codes are those of `complete`: nothing is printed unless it's 0.
+//! A failed `cd` returns from the shell function, so the steps after it,
+//! learning the jump and running the hook, only happen once the shell is
+//! there.
+
+use crate::hooks;
+use crate::init::Shell;
+use crate::messages;
+use eyre::{bail, eyre, WrapErr};
+use std::path::{Component, Path, PathBuf};
+
+pub enum Step {
+    Cd(PathBuf),
+    /// `wdbin confirm`, learning the jump
+    Confirm(Confirm),
+    /// shell code run as is, like the `on_jump` hook
+    Run(String),
+}
+
+/// A jump for `wdbin confirm` to learn.
+pub struct Confirm {
+    pub path: PathBuf,
+    pub from: Option<PathBuf>,
+    /// the input that matched `path` and its score, none if it wasn't
+    /// matched but typed as is or stepped to in a group
+    pub query: Option<(String, f64)>,
+}
+
+impl Confirm {
+    fn render(&self, shell: Shell) -> eyre::Result<String> {
+        // the binary printing this, whatever `$WDBIN` the function used
+        let exe = std::env::current_exe().wrap_err_with(messages::no_exe)?;
+        let quoted = |s: &str| hooks::quote(s, shell);
+        let path = quoted(checked(&self.path)?)?;
+        let mut command = format!(
+            "{} confirm",
+            quoted(
+                exe.to_str()
+                    .ok_or_else(|| eyre!(messages::not_unicode(&exe)))?
+            )?
+        );
+        if let Some(from) = &self.from {
+            command.push_str(&format!(" --from {}", quoted(checked(from)?)?));
+        }
+        if let Some((input, score)) = &self.query {
+            // attached, so a query starting with `-` isn't taken for a flag
+            command.push_str(&format!(" --query={} --score={score}", quoted(input)?));
+        }
+        command.push_str(&format!(" -- {path}"));
+        Ok(command)
+    }
+}
+
+/// `path` if it's fit to be handed to the shell: absolute and normalized,
+/// as wd stores them. Anything else came from a tampered or broken db.
+fn checked(path: &Path) -> eyre::Result<&str> {
+    let normal = path.is_absolute()
+        && path
+            .components()
+            .all(|c| matches!(c, Component::RootDir | Component::Normal(_)));
+    if !normal {
+        bail!(messages::not_normalized(path));
+    }
+    path.to_str()
+        .ok_or_else(|| eyre!(messages::not_unicode(path)))
+}
+
+pub fn render(steps: &[Step], shell: Shell) -> eyre::Result<String> {
+    let mut payload = String::new();
+    for step in steps {
+        match step {
+            Step::Cd(path) => {
+                let path = checked(path)?;
+                let path = hooks::quote(path, shell)?;
+                payload.push_str(&match shell {
+                    Shell::Fish => format!("builtin cd -- {path}; or return"),
+                    _ => format!("builtin cd -- {path} || return"),
+                });
+            }
+            Step::Confirm(confirm) => payload.push_str(&confirm.render(shell)?),
+            Step::Run(code) => payload.push_str(code),
+        }
+        payload.push('\n');
+    }
+    Ok(payload)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[test]
+    fn confirm_takes_queries_starting_with_a_dash() {
+        let confirm = Confirm {
+            path: PathBuf::from("/home/user/-foo"),
+            from: Some(PathBuf::from("/home/user")),
+            query: Some(("-fo".to_string(), 0.5)),
+        };
+        let line = confirm.render(Shell::Bash).unwrap();
+        let args = shell_words::split(&line).unwrap();
+        let opts = crate::Opts::try_parse_from(args).unwrap();
+        let crate::Action::Confirm {
+            path,
+            from,
+            query,
+            score,
+        } = opts.action
+        else {
+            panic!("not a confirm: {}", line);
+        };
+        assert_eq!(path, confirm.path);
+        assert_eq!(from, confirm.from);
+        assert_eq!(query.as_deref(), Some("-fo"));
+        assert!((score - 0.5).abs() < f64::EPSILON);
+    }
+}
@@ -0,0 +1,82 @@
+//! Remembering which directories exist for a little while, since checking
+//! every candidate gets slow on network filesystems and spinning disks.
+
+use crate::messages;
+use crate::store::{self, now};
+use eyre::Context;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+pub const DEFAULT_TTL: u64 = 60;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+struct Checked {
+    is_dir: bool,
+    at: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct ExistenceCache {
+    /// `None` when caching is disabled
+    file_path: Option<PathBuf>,
+    ttl: u64,
+    checked: HashMap<PathBuf, Checked>,
+    dirty: bool,
+}
+
+impl ExistenceCache {
+    /// A cache checking the filesystem every time.
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    pub fn open(file_path: PathBuf, ttl: u64) -> eyre::Result<Self> {
+        let checked = match std::fs::read(&file_path) {
+            Ok(raw) => serde_json::from_slice(&raw)
+                .wrap_err_with(|| messages::error_loading(&file_path))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(e).wrap_err_with(|| messages::error_loading(&file_path)),
+        };
+        Ok(Self {
+            file_path: Some(file_path),
+            ttl,
+            checked,
+            dirty: false,
+        })
+    }
+
+    pub fn is_dir(&mut self, path: &Path) -> bool {
+        if self.file_path.is_none() {
+            return path.is_dir();
+        }
+        let now = now();
+        if let Some(checked) = self.checked.get(path) {
+            if now.saturating_sub(checked.at) < self.ttl {
+                return checked.is_dir;
+            }
+        }
+        let is_dir = path.is_dir();
+        self.checked
+            .insert(path.to_path_buf(), Checked { is_dir, at: now });
+        self.dirty = true;
+        is_dir
+    }
+
+    /// Persist the cache if anything was checked, dropping expired entries.
+    pub fn write(&mut self) -> eyre::Result<()> {
+        let Some(file_path) = &self.file_path else {
+            return Ok(());
+        };
+        if !self.dirty {
+            return Ok(());
+        }
+        let (now, ttl) = (now(), self.ttl);
+        self.checked
+            .retain(|_, checked| now.saturating_sub(checked.at) < ttl);
+        store::write_private(file_path, &serde_json::to_vec(&self.checked)?)
+            .wrap_err_with(|| messages::error_writing(file_path))?;
+        self.dirty = false;
+        Ok(())
+    }
+}
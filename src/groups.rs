@@ -0,0 +1,52 @@
+//! Named groups of related directories, like the repositories of one
+//! product, cycled through with `wd next` and `wd prev` from anywhere inside
+//! one of them.
+
+use crate::store::Group;
+use std::cmp::Reverse;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Next,
+    Prev,
+}
+
+impl Direction {
+    /// `next` or `prev` typed as a query, the way `wd next` reaches here.
+    pub fn parse(input: &str) -> Option<Self> {
+        match input {
+            "next" => Some(Self::Next),
+            "prev" => Some(Self::Prev),
+            _ => None,
+        }
+    }
+}
+
+/// The group `dir` is in and the index of the member containing it. Within
+/// nested members the deepest one wins, then the first group by name.
+/// Groups of a single directory have nowhere to go and are left out.
+pub fn containing<'a>(groups: &'a [Group], dir: &Path) -> Option<(&'a Group, usize)> {
+    groups
+        .iter()
+        .filter(|g| g.paths.len() > 1)
+        .flat_map(|g| {
+            g.paths
+                .iter()
+                .enumerate()
+                .filter(|(_, path)| dir.starts_with(path))
+                .map(move |(i, path)| (g, i, path.components().count()))
+        })
+        .min_by_key(|&(_, _, depth)| Reverse(depth))
+        .map(|(g, i, _)| (g, i))
+}
+
+/// The member after (or before) the one at `index`, wrapping around.
+pub fn step(group: &Group, index: usize, direction: Direction) -> PathBuf {
+    let len = group.paths.len();
+    let index = match direction {
+        Direction::Next => (index + 1) % len,
+        Direction::Prev => (index + len - 1) % len,
+    };
+    group.paths[index].clone()
+}
@@ -0,0 +1,46 @@
+//! Showing which parts of listed paths matched the query.
+
+use crate::matcher;
+use std::io::IsTerminal;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ColorMode {
+    /// when writing to a terminal and `NO_COLOR` isn't set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorMode {
+    /// Whether to color output going to `stream`.
+    pub fn enabled(self, stream: &impl IsTerminal) -> bool {
+        match self {
+            Self::Always => true,
+            Self::Never => false,
+            Self::Auto => stream.is_terminal() && std::env::var_os("NO_COLOR").is_none(),
+        }
+    }
+}
+
+const MATCHED: &str = "\x1b[1;33m";
+const RESET: &str = "\x1b[0m";
+
+/// `path` with the characters matching `query` in bold yellow.
+pub fn paint(path: &str, query: &str) -> String {
+    let positions = matcher::positions(path, query);
+    let mut out = String::with_capacity(path.len() + positions.len() * 8);
+    let mut open = false;
+    for (i, c) in path.chars().enumerate() {
+        let matched = positions.binary_search(&i).is_ok();
+        if matched != open {
+            out.push_str(if matched { MATCHED } else { RESET });
+            open = matched;
+        }
+        out.push(c);
+    }
+    if open {
+        out.push_str(RESET);
+    }
+    out
+}
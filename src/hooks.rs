@@ -0,0 +1,27 @@
+//! Commands the shell function runs after a jump, configured as `on_jump`.
+//! They're printed for the shell to eval rather than run by wd, so they can
+//! change the shell itself, like activating a virtualenv.
+
+use crate::init::Shell;
+use crate::messages;
+use eyre::{bail, eyre};
+use std::path::Path;
+
+const PATH_PLACEHOLDER: &str = "{path}";
+
+/// `s` as a single word for `shell`.
+pub fn quote(s: &str, shell: Shell) -> eyre::Result<String> {
+    match shell {
+        Shell::Bash | Shell::Zsh => Ok(format!("'{}'", s.replace('\'', r"'\''"))),
+        Shell::Fish => Ok(format!("'{}'", s.replace('\\', r"\\").replace('\'', r"\'"))),
+        Shell::Tmux => bail!(messages::tmux_hooks()),
+    }
+}
+
+/// The hook `template` with `{path}` replaced by `path`, quoted for `shell`.
+pub fn render(template: &str, path: &Path, shell: Shell) -> eyre::Result<String> {
+    let path = path
+        .to_str()
+        .ok_or_else(|| eyre!(messages::not_unicode(path)))?;
+    Ok(template.replace(PATH_PLACEHOLDER, &quote(path, shell)?))
+}
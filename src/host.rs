@@ -0,0 +1,20 @@
+//! Telling machines apart when a database is synced between them.
+
+use std::process::Command;
+
+/// Name of this machine, if it can be found.
+pub fn hostname() -> Option<String> {
+    let from_file = |path| std::fs::read_to_string(path).ok();
+    let from_command = || {
+        Command::new("hostname")
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .and_then(|output| String::from_utf8(output.stdout).ok())
+    };
+    from_file("/proc/sys/kernel/hostname")
+        .or_else(|| from_file("/etc/hostname"))
+        .or_else(from_command)
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+}
@@ -0,0 +1,21 @@
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    /// not a shell, key bindings for tmux.conf driving `wdbin session`
+    Tmux,
+}
+
+impl Shell {
+    /// The `wd` function wrapping `wdbin complete` for this shell, or the
+    /// bindings for tmux.
+    pub const fn script(self) -> &'static str {
+        match self {
+            Self::Bash => include_str!("init/wd.bash"),
+            Self::Zsh => include_str!("init/wd.zsh"),
+            Self::Fish => include_str!("init/wd.fish"),
+            Self::Tmux => include_str!("init/wd.tmux"),
+        }
+    }
+}
@@ -0,0 +1,22 @@
+# Pickers can list candidates with `wdbin complete --list -- query`, which
+# doesn't learn anything, then report the chosen one with
+# `wdbin track --from "$PWD" -- "$dir"` so it's learned like a jump.
+# wdbin prints the shell code making the jump, see `eval.rs`.
+function wd () {
+  local payload
+  payload=$("${WDBIN:-"wdbin"}" __jump --shell bash --from "$PWD" -- "$@") || return
+  eval "$payload"
+}
+
+# TAB completion for wd (and cd, which it usually replaces): the usual
+# directories, or wd's history matches when no directory matches the word.
+# Mixing both would leave bash no common prefix to complete.
+_wd_complete() {
+  local cur=${COMP_WORDS[COMP_CWORD]} IFS=$'\n' dir
+  COMPREPLY=($(compgen -d -- "$cur"))
+  [ ${#COMPREPLY[@]} -gt 0 ] && return
+  while IFS= read -r -d '' dir; do
+    COMPREPLY+=("$dir")
+  done < <("${WDBIN:-"wdbin"}" --print0 __complete-dirs -- "$cur" 2>/dev/null)
+}
+complete -o nosort -o filenames -F _wd_complete wd cd
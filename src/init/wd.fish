@@ -0,0 +1,25 @@
+# Pickers can list candidates with `wdbin complete --list -- query`, which
+# doesn't learn anything, then report the chosen one with
+# `wdbin track --from "$PWD" -- "$dir"` so it's learned like a jump.
+# wdbin prints the shell code making the jump, see `eval.rs`.
+function wd
+  set -l wdbin wdbin
+  set -q WDBIN; and set wdbin $WDBIN
+
+  set -l payload ($wdbin __jump --shell fish --from "$PWD" -- $argv); or return
+  string join \n -- $payload | source
+end
+
+# TAB completion for wd (and cd, which it usually replaces): the usual
+# directories, then wd's history matches described as such.
+function __wd_history
+  set -l wdbin wdbin
+  set -q WDBIN; and set wdbin $WDBIN
+  # completions are one per line, names with a newline can't be offered
+  for dir in ($wdbin --print0 __complete-dirs -- (commandline -ct) 2>/dev/null | string split0)
+    string match -q -- '*'\n'*' $dir; or printf '%s\n' $dir
+  end
+end
+complete -c wd -f -a '(__fish_complete_directories (commandline -ct))'
+complete -c wd -f -k -a '(__wd_history)' -d 'wd history'
+complete -c cd -f -k -a '(__wd_history)' -d 'wd history'
@@ -2,238 +2,2402 @@
 // #![warn(clippy::pedantic)]
 #![warn(clippy::nursery)]
 
-use clap::{Parser, Subcommand};
-use dirs::data_dir;
-use eyre::{Context, OptionExt};
-use serde::{Deserialize, Serialize};
-use std::fs::{File, OpenOptions};
-use std::io::{BufReader, BufWriter, Error as IOError, ErrorKind};
-use std::path::{Path, PathBuf};
-use std::time::Instant;
-use strsim::normalized_damerau_levenshtein;
-
-#[derive(Debug, Serialize, Deserialize, Clone, Default)]
-struct DBContent {
-    paths: Vec<PathBuf>,
-}
-impl DBContent {
-    const fn new() -> Self {
-        Self { paths: vec![] }
-    }
-}
-#[derive(Debug, Clone)]
-struct DB {
-    file_path: String,
-    content: DBContent,
+use cache::QueryCache;
+use clap::builder::FalseyValueParser;
+use clap::{Args, CommandFactory, Parser, Subcommand};
+use config::{Config, Inaccessible, StoreKind};
+use dialoguer::{MultiSelect, Select};
+use error::WdError;
+use exists::ExistenceCache;
+use eyre::Context;
+use globset::GlobBuilder;
+use highlight::ColorMode;
+use matcher::{rank, weight, TRANSITION_BOOST};
+use notify::Watcher;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+use std::sync::mpsc::RecvTimeoutError;
+use std::time::{Duration, Instant};
+use store::{Entry, Store, Suppression};
+use tune::Stats;
+use watch::Change;
+
+mod access;
+mod assets;
+mod bench;
+mod cache;
+mod clipboard;
+mod config;
+mod error;
+mod eval;
+mod exists;
+mod groups;
+mod highlight;
+mod hooks;
+mod host;
+mod init;
+mod maintenance;
+mod matcher;
+mod messages;
+mod migrate;
+mod open;
+mod output;
+mod plumbing;
+mod private;
+mod projects;
+mod session;
+#[cfg(feature = "simulate")]
+mod simulate;
+mod sources;
+mod status;
+mod store;
+mod sync;
+mod temporal;
+mod time;
+mod translit;
+mod tune;
+mod update;
+mod watch;
+
+/// Where a match came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Origin {
+    /// the input was an existing directory
+    Filesystem,
+    /// one of the candidate sources, by name
+    Source(&'static str),
 }
 
-impl DB {
-    fn open(db_path: Option<&str>) -> Result<Self, IOError> {
-        let file_path = db_path
-            .map(|p| p.to_string())
-            .unwrap_or_else(Self::default_db_path);
+struct CompleteResult {
+    confidence: f64,
+    path: PathBuf,
+    source: Origin,
+}
 
-        match File::open(file_path.clone()) {
-            Ok(file) => Ok(Self {
-                file_path,
-                content: serde_json::from_reader(BufReader::new(file))?,
-            }),
-            Err(e) => {
-                if e.kind() == ErrorKind::NotFound {
-                    Ok(Self {
-                        file_path,
-                        content: DBContent::new(),
-                    })
-                } else {
-                    Err(e)
-                }
-            }
+impl CompleteResult {
+    const fn new(confidence: f64, path: PathBuf, source: Origin) -> Self {
+        Self {
+            confidence,
+            path,
+            source,
         }
     }
 
-    fn paths(&self) -> &[PathBuf] {
-        &self.content.paths
+    /// What `confirm` should learn once a jump for `query` got here. A
+    /// directory typed as is is learned as a visit, like with `track`.
+    fn confirm(&self, query: &Query) -> eval::Confirm {
+        eval::Confirm {
+            path: self.path.clone(),
+            from: query.from.clone(),
+            query: (self.source != Origin::Filesystem)
+                .then(|| (query.input.clone(), self.confidence)),
+        }
     }
+}
 
-    fn write(&self) -> Result<(), IOError> {
-        let file = OpenOptions::new()
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .open(&self.file_path)?;
-        let writer = BufWriter::new(file);
-        serde_json::to_writer(writer, &self.content)?;
-        Ok(())
+/// `path` relative to `base` (`../sibling/src`), if that is shorter than the
+/// absolute path.
+fn shortest_relative(path: &Path, base: &Path) -> PathBuf {
+    let common = path
+        .components()
+        .zip(base.components())
+        .take_while(|(a, b)| a == b)
+        .count();
+    if common == 0 {
+        return path.to_path_buf();
     }
 
-    fn bump(&mut self, path: PathBuf) -> Result<&mut Self, IOError> {
-        let abspath: PathBuf = (*path).into();
-        self.content.paths.retain(|p| p != &abspath);
-        self.content.paths.insert(0, abspath);
-        Ok(self)
+    let mut relative: PathBuf = base.components().skip(common).map(|_| "..").collect();
+    relative.extend(path.components().skip(common));
+    if relative.as_os_str().is_empty() {
+        relative.push(".");
     }
 
-    fn forget(&mut self, path: PathBuf) -> Result<&mut Self, IOError> {
-        self.content.paths.retain(|p| p != &path);
-        Ok(self)
+    if relative.as_os_str().len() < path.as_os_str().len() {
+        relative
+    } else {
+        path.to_path_buf()
     }
+}
+
+/// How many matches `--list` asks for, 0 meaning all of them, capped by
+/// `--max-results`.
+fn list_limit(list: Option<usize>, max_results: Option<usize>) -> Option<usize> {
+    list.map(|list| if list == 0 { usize::MAX } else { list })
+        .map(|list| max_results.map_or(list, |max| list.min(max)))
+}
 
-    fn default_db_path() -> String {
-        let mut a = data_dir().unwrap_or_else(|| "/tmp/".into());
-        a.push("wd/wddb");
-        a.to_string_lossy().into()
+/// `path` made absolute with `.` and `..` resolved without touching the
+/// filesystem, for paths that may not exist anymore.
+fn normalize(path: &Path) -> eyre::Result<PathBuf> {
+    let mut normalized = if path.is_absolute() {
+        PathBuf::new()
+    } else {
+        std::env::current_dir()?
+    };
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            c => normalized.push(c),
+        }
     }
+    Ok(normalized)
 }
 
-struct CompleteResult {
-    confidence: f64,
-    path: PathBuf,
+#[derive(Debug, Clone, Subcommand)]
+pub enum DbAction {
+    /// check every entry and report problems, without changing anything
+    Verify,
+    /// fix what `verify` reports and rewrite the database
+    Compact,
 }
 
-impl CompleteResult {
-    const fn new(confidence: f64, path: PathBuf) -> Self {
-        Self { confidence, path }
-    }
+#[derive(Debug, Clone, Subcommand)]
+pub enum PrivateAction {
+    /// never record this directory nor anything under it again, and forget
+    /// what was recorded
+    Add { path: PathBuf },
+    /// print the private directories
+    List,
 }
 
-fn weight(index: usize) -> f64 {
-    1.2 - (0.4 / (1. + (index as f64 / -2.).exp()))
+#[derive(Debug, Clone, Subcommand)]
+pub enum GroupAction {
+    /// add directories, the current one if none, at the end of a group,
+    /// creating it if needed
+    Add { name: String, dirs: Vec<PathBuf> },
+    /// remove directories from a group, or the whole group if none are given
+    Remove { name: String, dirs: Vec<PathBuf> },
+    /// print every group with its directories, or only the directories of
+    /// `name`
+    List { name: Option<String> },
 }
 
-fn dist(path: &Path, query: &str) -> eyre::Result<f64> {
-    let path_str = path.to_str().ok_or_eyre("couldn't turn path to str")?;
-    let basename = path.file_name().and_then(|s| s.to_str());
+/// Options of everything resolving a query.
+#[derive(Debug, Clone, Args)]
+pub struct QueryArgs {
+    input: String,
 
-    let full_dist = normalized_damerau_levenshtein(path_str, query);
-    let base_dist = basename
-        .map(|n| normalized_damerau_levenshtein(n, query))
-        .unwrap_or(0.);
-    let base_icase_dist = basename
-        .map(|n| {
-            normalized_damerau_levenshtein(&n.to_ascii_lowercase(), &query.to_ascii_lowercase())
-        })
-        .unwrap_or(0.);
+    /// minimum confidence, defaults to the config's or 0.4
+    #[clap(short = 'c', long = "confidence")]
+    confidence: Option<f64>,
+
+    /// only match paths containing the input as is, ignoring case
+    #[clap(short = 'e', long = "exact")]
+    exact: bool,
+
+    /// directory the jump starts from, defaults to the current one
+    #[clap(long = "from")]
+    from: Option<PathBuf>,
+
+    /// leave out the directory the jump starts from
+    #[clap(long = "exclude-cwd")]
+    exclude_cwd: bool,
+
+    /// leave out the parents of the directory the jump starts from
+    #[clap(long = "exclude-ancestors")]
+    exclude_ancestors: bool,
+
+    /// leave out this directory, can be repeated
+    #[clap(long = "exclude", value_name = "DIR")]
+    exclude: Vec<PathBuf>,
+}
 
-    Ok(full_dist.max(base_dist).max(base_icase_dist * 0.9))
+/// A query with its defaults resolved.
+#[derive(Clone)]
+struct Query {
+    input: String,
+    min_confidence: f64,
+    exact: bool,
+    from: Option<PathBuf>,
+    exclude: Exclusions,
+}
+
+/// Directories left out of a query's matches after scoring.
+#[derive(Debug, Clone, Default)]
+struct Exclusions {
+    paths: Vec<PathBuf>,
+    /// leave out the parents of this directory
+    ancestors_of: Option<PathBuf>,
+}
+
+impl Exclusions {
+    fn contains(&self, path: &Path) -> bool {
+        self.paths.iter().any(|p| p == path)
+            || self
+                .ancestors_of
+                .as_deref()
+                .is_some_and(|dir| dir != path && dir.starts_with(path))
+    }
 }
 
 #[derive(Debug, Clone, Subcommand)]
 pub enum Action {
     Complete {
+        #[command(flatten)]
+        query: QueryArgs,
+
+        /// print up to this many matches instead of the best one, all of them
+        /// without a value or with 0. The count is attached (`--list=5`), so
+        /// `--list query` lists matches for the query. Listing doesn't learn
+        /// anything, pickers report the chosen match with `wdbin track`
+        #[clap(
+            short = 'l',
+            long = "list",
+            num_args = 0..=1,
+            require_equals = true,
+            default_missing_value = "0"
+        )]
+        list: Option<usize>,
+
+        /// when listing, fill up to this many matches with the best ones below
+        /// the confidence threshold
+        #[clap(long = "min-results", requires = "list", default_value = "0")]
+        min_results: usize,
+
+        /// when listing, never print more than this many matches
+        #[clap(long = "max-results", requires = "list")]
+        max_results: Option<usize>,
+
+        /// print matches relative to the current directory when that's shorter
+        #[clap(short = 'r', long = "relative")]
+        relative: bool,
+
+        /// put the matches on the clipboard instead of printing them, without
+        /// learning from it
+        #[clap(long = "copy")]
+        copy: bool,
+
+        /// when listing, print matches as soon as they're scored, in history
+        /// order rather than best first, for pickers like fzf that sort on
+        /// their own
+        #[clap(long = "stream", requires = "list", conflicts_with = "copy")]
+        stream: bool,
+    },
+    Forget {
+        input: Option<String>,
+
+        /// pick entries to forget from a list, ranked against `input` if given
+        #[clap(short = 'i', long = "interactive")]
+        interactive: bool,
+
+        /// forget every entry matching a glob, like `/mnt/old/**`, whether
+        /// the directories still exist or not
+        #[clap(short = 'p', long = "pattern", conflicts_with_all = ["input", "interactive"])]
+        pattern: Option<String>,
+
+        /// only leave the entry out of matches for a while, like `2w` or
+        /// `1d12h`, keeping its history
+        #[clap(short = 't', long = "temporarily", value_parser = time::parse_duration)]
+        temporarily: Option<u64>,
+    },
+    /// print the best match like complete, but never learn from it, for
+    /// scripts like `cp file "$(wdbin which proj)"/`
+    Which {
+        #[command(flatten)]
+        query: QueryArgs,
+    },
+    /// print the most recent jumps with when they happened
+    Last {
+        #[clap(default_value = "10")]
+        count: usize,
+    },
+    /// print the jump log, most recent first, to find when you last worked
+    /// somewhere
+    History {
+        /// only jumps to directories matching this query
+        #[clap(short = 'q', long = "query")]
+        query: Option<String>,
+
+        #[clap(short = 'n', long = "limit")]
+        limit: Option<usize>,
+    },
+    /// print what wd knows about a directory, the current one by default
+    Status {
+        path: Option<PathBuf>,
+
+        /// stable single line output for prompts, see `status.rs`
+        #[clap(long = "porcelain")]
+        porcelain: bool,
+    },
+    /// print every entry, most recent first, with how long snoozed ones are
+    /// left out of matches and which ones were only visited on other hosts
+    List {
+        /// put the paths on the clipboard instead of printing them
+        #[clap(long = "copy")]
+        copy: bool,
+    },
+    /// named groups of related directories, cycled through with `wd next`
+    /// and `wd prev`
+    Group {
+        #[command(subcommand)]
+        action: GroupAction,
+    },
+    /// print the directory after the current one in its group, where
+    /// `wd next` goes
+    Next,
+    /// print the directory before the current one in its group, where
+    /// `wd prev` goes
+    Prev,
+    /// directories wd never records nor lists, see `private_paths`
+    Private {
+        #[command(subcommand)]
+        action: PrivateAction,
+    },
+    /// database maintenance
+    Db {
+        #[command(subcommand)]
+        action: DbAction,
+    },
+    /// merge jump history with another copy of the database, either a local
+    /// file or `[user@]host:path` fetched over scp
+    Sync {
+        remote: String,
+
+        /// only merge the remote into the local db, don't write it back
+        #[clap(long = "no-push")]
+        no_push: bool,
+    },
+    /// time matching against a synthetic database
+    Bench {
+        #[clap(default_value = "project")]
         input: String,
 
+        /// number of entries in the synthetic database
+        #[clap(short = 's', long = "size", default_value = "50000")]
+        size: usize,
+
+        #[clap(short = 'n', long = "iterations", default_value = "10")]
+        iterations: usize,
+
         #[clap(short = 'c', long = "confidence", default_value = "0.4")]
         confidence: f64,
+    },
+    /// replay a navigation trace against an in-memory copy of the db and
+    /// report how often the ranking got the target, see `simulate.rs`
+    #[cfg(feature = "simulate")]
+    #[command(hide = true)]
+    Simulate {
+        trace: PathBuf,
 
-        #[clap(short = 'l', long = "list")]
-        list: Option<usize>,
+        /// start from this json db instead of an empty one
+        #[clap(long = "seed")]
+        seed: Option<PathBuf>,
+
+        /// also print the steps whose target wasn't the best match
+        #[clap(long = "misses")]
+        misses: bool,
     },
-    Forget {
-        input: Option<String>,
+    /// machine readable completion for integrations, see `plumbing.rs` for
+    /// the output format. Never writes to the db.
+    #[command(name = "__complete", hide = true)]
+    Plumbing {
+        #[command(flatten)]
+        query: QueryArgs,
+
+        /// how many matches to print, 0 for all of them
+        #[clap(short = 'l', long = "list", default_value = "1")]
+        list: usize,
+
+        /// protocol version the caller expects
+        #[clap(long = "protocol", default_value = "1")]
+        protocol: u32,
+    },
+    /// history matches for TAB completion, see `plumbing.rs`
+    #[command(name = "__complete-dirs", hide = true)]
+    CompleteDirs {
+        #[clap(default_value = "")]
+        word: String,
+
+        #[clap(short = 'l', long = "list", default_value = "10")]
+        list: usize,
+    },
+    /// resolve the input like complete, then launch a program in the match
+    Open {
+        #[command(flatten)]
+        query: QueryArgs,
+
+        /// program to launch: `editor`, `files`, `code` or any name from the
+        /// config's `[open.programs]`
+        #[clap(short = 'w', long = "with")]
+        program: Option<String>,
+    },
+    /// resolve the input like complete, then attach or switch to a tmux
+    /// session named after the match, created there if needed
+    Session {
+        #[command(flatten)]
+        query: QueryArgs,
+    },
+    /// move every stored path under OLD_HOME to NEW_HOME, after a user name
+    /// or home directory change
+    MigrateHome {
+        old_home: PathBuf,
+        new_home: PathBuf,
+
+        /// also rename a host tag, as `old=new`
+        #[clap(long = "host", value_name = "OLD=NEW")]
+        host: Option<String>,
+    },
+    /// add every project found under some directories to the db, as never
+    /// visited, to bootstrap the history on a new machine
+    Projects {
+        /// directories to look for projects in
+        #[clap(long = "scan", required = true)]
+        scan: Vec<PathBuf>,
+
+        /// how many levels below each directory to look
+        #[clap(long = "depth", default_value = "3")]
+        depth: usize,
+
+        /// file or directory marking a project, replaces the config's
+        /// `[projects] markers` and the built-in list (.git, Cargo.toml, ...)
+        #[clap(long = "marker")]
+        markers: Vec<String>,
+    },
+    /// keep running and forget directories as soon as they're deleted, or
+    /// move their history along when they're renamed
+    Watch,
+    /// suggest a default confidence from the scores of matches you used or
+    /// forgot right after
+    Tune {
+        /// write the suggestion to the config file
+        #[clap(long = "apply")]
+        apply: bool,
     },
-    // TODO: Init,
+    /// learn a jump to a directory picked from `complete --list`, as if
+    /// `complete` had resolved to it
+    Track {
+        path: PathBuf,
+
+        /// directory the jump started from, the current one by default
+        #[clap(long = "from")]
+        from: Option<PathBuf>,
+    },
+    /// learn a jump once the shell got to PATH, what `wd` runs after a
+    /// successful cd so only directories actually reached are learned
+    Confirm {
+        path: PathBuf,
+
+        /// directory the jump started from, left unlearned if not given
+        #[clap(long = "from")]
+        from: Option<PathBuf>,
+
+        /// input that resolved to PATH, learned like `complete` resolving it;
+        /// like `track` without it
+        #[clap(long = "query")]
+        query: Option<String>,
+
+        /// score PATH had for the query
+        #[clap(long = "score", requires = "query", default_value_t = 1.)]
+        score: f64,
+    },
+    /// resolve the input like `complete`, asking which candidate to take when
+    /// the best one isn't sure enough, and print shell code going there, for the shell
+    /// function to eval, learning it only once there. See `eval.rs`.
+    #[command(name = "__jump", hide = true)]
+    Jump {
+        #[command(flatten)]
+        query: QueryArgs,
+
+        #[clap(long = "shell", value_enum, default_value = "bash")]
+        shell: init::Shell,
+    },
+    /// print the `on_jump` hook for the shell function to eval after a jump
+    #[command(name = "exec-hooks", hide = true)]
+    ExecHooks {
+        /// directory jumped to, the current one by default
+        path: Option<PathBuf>,
+
+        #[clap(long = "shell", value_enum, default_value = "bash")]
+        shell: init::Shell,
+    },
+    /// write the init scripts, shell completions and man page to a directory,
+    /// for packagers
+    GenerateAssets {
+        #[clap(long = "out")]
+        out: PathBuf,
+    },
+    /// replace this binary with the latest GitHub release for the platform.
+    /// The release's sha256 only catches corrupted downloads, not tampering
+    SelfUpdate {
+        /// only tell whether a newer release exists
+        #[clap(long = "check")]
+        check: bool,
+    },
+    /// print the shell function to put in your shell's rc file, as in
+    /// `eval "$(wdbin init bash)"`
+    Init { shell: init::Shell },
 }
 
 #[derive(Parser)]
 #[clap(version=env!("CARGO_PKG_VERSION"), author = "obayemi")]
 struct Opts {
     #[clap(long = "db")]
-    db_path: Option<String>,
+    db_path: Option<PathBuf>,
+
+    /// config file, defaults to `$XDG_CONFIG_HOME/wd/config.toml`
+    #[clap(long = "config")]
+    config_path: Option<PathBuf>,
+
+    /// profile to use, with its own db and `[profiles.<name>]` config
+    /// overrides
+    #[clap(long = "profile", env = "WD_PROFILE")]
+    profile: Option<String>,
+
+    /// storage backend, overriding the config
+    #[clap(long = "store", value_enum)]
+    store: Option<StoreKind>,
+
+    #[clap(skip)]
+    config: Config,
 
     #[clap(short = 'd', long = "debug")]
     debug: bool,
 
+    /// highlight the parts of listed paths matching the query
+    #[clap(long = "color", value_enum, default_value = "auto")]
+    color: ColorMode,
+
+    /// end printed paths and records with a NUL instead of a newline, and
+    /// never escape them, for `xargs -0` or `read -d ''`
+    #[clap(short = '0', long = "print0", global = true)]
+    print0: bool,
+
+    /// write the directory found by `complete`, `which`, `next` or `prev` to
+    /// this file instead of printing it, for editors and file managers that
+    /// read the result from a file (`/dev/fd/N` for a file descriptor)
+    #[clap(long = "chdir-file", value_name = "FILE", global = true)]
+    chdir_file: Option<PathBuf>,
+
+    /// always check whether directories exist and rank matches again instead
+    /// of trusting recent results
+    #[clap(long = "no-cache")]
+    no_cache: bool,
+
+    /// never write anything: no learning from jumps, no db creation
+    #[clap(
+        long = "read-only",
+        alias = "no-learn",
+        env = "WD_READONLY",
+        value_parser = FalseyValueParser::new()
+    )]
+    read_only: bool,
+
     #[command(subcommand)]
     action: Action,
 }
 
+/// Exit codes, the shell integrations rely on them.
+const EXIT_OK: i32 = 0;
+const EXIT_NO_MATCH: i32 = 1;
+/// anything going wrong, like clap's usage errors
+const EXIT_ERROR: i32 = 2;
+
+const DEFAULT_CONFIDENCE: f64 = 0.4;
+const DEFAULT_SURE_CONFIDENCE: f64 = 0.7;
+/// how many candidates the menu of `wd` offers at most
+const SELECT_CANDIDATES: usize = 9;
+/// exit code of `wd` when several candidates are close and none was picked
+const EXIT_AMBIGUOUS: i32 = 3;
+
+enum Completion {
+    Matches(Vec<CompleteResult>),
+    Ambiguous(Vec<CompleteResult>),
+}
+
 impl Opts {
+    fn confidence(&self, flag: Option<f64>) -> f64 {
+        flag.or(self.config.confidence)
+            .unwrap_or(DEFAULT_CONFIDENCE)
+    }
+
+    fn query(&self, args: &QueryArgs) -> Query {
+        let from = args
+            .from
+            .clone()
+            .or_else(|| std::env::current_dir().ok())
+            .and_then(|from| from.canonicalize().ok());
+        let exclude = self.exclusions(
+            from.as_deref(),
+            args.exclude_cwd,
+            args.exclude_ancestors,
+            &args.exclude,
+        );
+        Query {
+            input: args.input.clone(),
+            min_confidence: self.confidence(args.confidence),
+            exact: args.exact,
+            from,
+            exclude,
+        }
+    }
+
+    /// What to leave out of matches for a jump from `from`, from the flags
+    /// and the config's `[exclude]` together.
+    fn exclusions(
+        &self,
+        from: Option<&Path>,
+        cwd: bool,
+        ancestors: bool,
+        paths: &[PathBuf],
+    ) -> Exclusions {
+        let config = &self.config.exclude;
+        let mut excluded: Vec<PathBuf> = paths
+            .iter()
+            .chain(&config.paths)
+            .filter_map(|path| path.canonicalize().or_else(|_| normalize(path)).ok())
+            .collect();
+        if cwd || config.cwd {
+            excluded.extend(from.map(Path::to_path_buf));
+        }
+        Exclusions {
+            paths: excluded,
+            ancestors_of: from
+                .filter(|_| ancestors || config.ancestors)
+                .map(Path::to_path_buf),
+        }
+    }
+
+    /// How `path` is shown in listings and menus, flagged when the user
+    /// can't enter it and the config keeps such matches.
+    fn shown(&self, path: &Path, out: output::Output) -> String {
+        let shown = out.show(path);
+        if self.config.inaccessible == Inaccessible::Mark && !access::can_enter(path) {
+            return format!("{shown} (no access)");
+        }
+        shown.into_owned()
+    }
+
+    /// Print a match of `complete`, relative to `cwd` if given, with the
+    /// parts matching `highlight` colored if given.
+    fn print_match(
+        &self,
+        m: &CompleteResult,
+        cwd: Option<&Path>,
+        highlight: Option<&str>,
+    ) -> std::io::Result<()> {
+        let out = self.output();
+        let path = cwd.map_or_else(|| m.path.clone(), |cwd| shortest_relative(&m.path, cwd));
+        if self.debug {
+            out.record(&format!("[{:.2}] {}", m.confidence, self.shown(&path, out)))
+        } else if let Some(input) = highlight {
+            out.record(&highlight::paint(&self.shown(&path, out), input))
+        } else if out.human {
+            out.record(&self.shown(&path, out))
+        } else {
+            out.path(&path)
+        }
+    }
+
+    /// Hand over the single directory a command resolved to: written to
+    /// `--chdir-file` if given, followed by a newline (or a NUL with
+    /// `--print0`), printed otherwise.
+    fn print_dir(&self, path: &Path) -> eyre::Result<()> {
+        let Some(file) = &self.chdir_file else {
+            return Ok(self.output().path(path)?);
+        };
+        let mut content = path.as_os_str().to_owned();
+        content.push(if self.print0 { "\0" } else { "\n" });
+        #[cfg(unix)]
+        let content = std::os::unix::ffi::OsStringExt::into_vec(content);
+        #[cfg(not(unix))]
+        let content = content.to_string_lossy().into_owned();
+        std::fs::write(file, content).wrap_err_with(|| messages::error_writing(file))
+    }
+
+    fn output(&self) -> output::Output {
+        output::Output::stdout(self.print0)
+    }
+
+    /// This machine's name when entries are tagged with hosts.
+    fn host(&self) -> Option<String> {
+        if !self.config.hosts.tag {
+            return None;
+        }
+        self.config.hosts.name.clone().or_else(host::hostname)
+    }
+
+    fn open_stats(&self) -> eyre::Result<Stats> {
+        let path = store::sidecar_path(self.db_path.as_deref(), &self.config, "stats");
+        if self.read_only {
+            Stats::open_read_only(path)
+        } else {
+            Stats::open(path)
+        }
+    }
+
+    fn open_existence_cache(&self) -> eyre::Result<ExistenceCache> {
+        if self.no_cache || self.read_only {
+            return Ok(ExistenceCache::disabled());
+        }
+        ExistenceCache::open(
+            store::sidecar_path(self.db_path.as_deref(), &self.config, "exists"),
+            self.config
+                .existence_cache_ttl
+                .unwrap_or(exists::DEFAULT_TTL),
+        )
+    }
+
+    fn open_query_cache(&self) -> QueryCache {
+        if self.no_cache || self.read_only {
+            return QueryCache::disabled();
+        }
+        let db_path = store::db_file_path(self.db_path.as_deref(), &self.config);
+        let fingerprint = cache::fingerprint(&[&db_path], &format!("{:?}", self.config));
+        let path = store::sidecar_path(self.db_path.as_deref(), &self.config, "queries");
+        QueryCache::open(path, fingerprint).unwrap_or_else(|err| {
+            eprintln!("{}", messages::ignoring_unreadable("query cache", &err));
+            QueryCache::disabled()
+        })
+    }
+
+    fn open_store(&self) -> eyre::Result<Box<dyn Store>> {
+        if self.read_only {
+            return store::open_read_only(self.db_path.as_deref(), &self.config)
+                .wrap_err_with(messages::loading_db);
+        }
+        store::open(self.db_path.as_deref(), &self.config).wrap_err_with(messages::loading_db)
+    }
+
+    /// For commands whose whole point is changing the db.
+    fn ensure_writable(&self) -> eyre::Result<()> {
+        if self.read_only {
+            return Err(WdError::ReadOnly.into());
+        }
+        Ok(())
+    }
+
+    /// Resolve `query` to its best match, or to up to `list` matches, at
+    /// least `min_results` if there are that many candidates at all. A best
+    /// match is learned right away unless `defer` leaves that to `confirm`,
+    /// misses always are. With `select` set to a "sure" confidence, a top
+    /// match below it with other candidates passing the minimum confidence is
+    /// ambiguous, and the candidates are returned for the user to pick one
+    /// instead, learning nothing.
     fn complete(
         &self,
-        input: &str,
-        min_confidence: f64,
+        query: &Query,
         list: Option<usize>,
-    ) -> eyre::Result<Vec<CompleteResult>> {
-        let mut db = DB::open(self.db_path.as_deref()).wrap_err("error loading wd db")?;
+        min_results: usize,
+        select: Option<f64>,
+        defer: bool,
+    ) -> eyre::Result<Completion> {
+        let mut db = self.open_store()?;
+        let mut stats = self.open_stats().unwrap_or_else(|err| {
+            eprintln!("{}", messages::ignoring_unreadable("stats", &err));
+            Stats::default()
+        });
 
         let now = Instant::now();
+        let limit = match (list, select) {
+            (Some(list), _) => list,
+            (None, Some(_)) => SELECT_CANDIDATES,
+            (None, None) => 1,
+        };
+        let mut matches = self.lookup(Some(db.as_ref()), query, limit)?;
+        if matches.len() < min_results.min(limit) {
+            let relaxed = Query {
+                min_confidence: 0.,
+                ..query.clone()
+            };
+            matches = self.lookup(Some(db.as_ref()), &relaxed, min_results.min(limit))?;
+        }
+
+        if let (None, Some(sure)) = (list, select) {
+            if matches.len() > 1 && matches[0].confidence < sure {
+                return Ok(Completion::Ambiguous(matches));
+            }
+            matches.truncate(1);
+        }
+
+        // misses are still learned, there's nothing to confirm
+        let learn_now = !defer || matches.is_empty();
+        if learn_now {
+            if let Err(err) = self.learn(db.as_mut(), &mut stats, query, matches.first(), list) {
+                // still hand out the match, a full disk shouldn't break cd
+                eprintln!("{}", messages::couldnt_record_jump(&err));
+            }
+        }
+        if self.debug {
+            println!("time: {:.2} ms", now.elapsed().as_micros() as f64 / 1000.)
+        }
+        Ok(Completion::Matches(matches))
+    }
+
+    /// Update the db and stats after `query` resolved to `item`.
+    fn learn(
+        &self,
+        db: &mut dyn Store,
+        stats: &mut Stats,
+        query: &Query,
+        item: Option<&CompleteResult>,
+        list: Option<usize>,
+    ) -> eyre::Result<()> {
+        let Some(item) = item else {
+            if list.is_none() {
+                if self.scrub_private(db, stats)? {
+                    db.flush().wrap_err_with(messages::writing_db)?;
+                }
+                let entries = db.entries()?;
+                stats.missed(
+                    &query.input,
+                    rank(&entries, &query.input, 0., &self.config.transliterate)?
+                        .first()
+                        .copied(),
+                );
+                stats.write()?;
+            }
+            return Ok(());
+        };
+
+        if self.scrub_private(db, stats)? {
+            db.flush().wrap_err_with(messages::writing_db)?;
+        }
+        if self.is_private(&item.path) {
+            return Ok(());
+        }
+        if item.source == Origin::Filesystem || list.is_none() {
+            self.learn_correction(
+                db,
+                stats,
+                query.from.as_deref(),
+                &item.path,
+                query.min_confidence,
+            )?;
+            if let Some(from) = &query.from {
+                if *from != item.path && !self.is_private(from) {
+                    db.record_transition(from.clone(), item.path.clone())?;
+                }
+            }
+        }
+        if item.source == Origin::Filesystem {
+            db.bump(item.path.clone(), self.host().as_deref())?;
+            db.flush().wrap_err_with(messages::writing_db)?;
+            stats.visited(&item.path);
+            stats.write()?;
+        } else if list.is_none() {
+            db.bump(item.path.clone(), self.host().as_deref())?;
+            db.flush().wrap_err_with(messages::writing_db)?;
+            stats.jumped(&query.input, &item.path, item.confidence);
+            stats.write()?;
+        }
+        Ok(())
+    }
+
+    fn is_private(&self, path: &Path) -> bool {
+        private::is_private(path, &self.config.private_paths)
+    }
+
+    /// Remove whatever the db and the stats still know about private
+    /// directories, if anything, and drop the caches that may name them too.
+    /// Returns whether the db changed and needs flushing.
+    fn scrub_private(&self, db: &mut dyn Store, stats: &mut Stats) -> eyre::Result<bool> {
+        if self.config.private_paths.is_empty() {
+            return Ok(false);
+        }
+        let in_stats = stats.scrub(|path| self.is_private(path));
+        let in_db = db.entries()?.iter().any(|e| self.is_private(&e.path))
+            || db.jumps()?.iter().any(|j| self.is_private(&j.path));
+        if !in_stats && !in_db {
+            return Ok(false);
+        }
+        self.drop_caches();
+        if in_stats {
+            stats.write()?;
+        }
+        if in_db {
+            let mut content = db.export()?;
+            private::scrub(&mut content, &self.config.private_paths);
+            db.import(content)?;
+        }
+        Ok(in_db)
+    }
+
+    /// Replace what `db` holds with `content`, leaving out private
+    /// directories whatever the content came from.
+    fn import(&self, db: &mut dyn Store, mut content: store::DBContent) -> eyre::Result<()> {
+        private::scrub(&mut content, &self.config.private_paths);
+        db.import(content)
+    }
+
+    /// Delete the query, existence and repository caches, rebuilt as needed.
+    fn drop_caches(&self) {
+        for sidecar in ["queries", "exists", "repos"] {
+            let path = store::sidecar_path(self.db_path.as_deref(), &self.config, sidecar);
+            match std::fs::remove_file(&path) {
+                Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+                    eprintln!("{}", messages::couldnt_remove(&path, &err));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// The single best match for commands acting on it.
+    fn resolve(&self, args: &QueryArgs) -> eyre::Result<CompleteResult> {
+        let completion = self.complete(&self.query(args), None, 0, None, false)?;
+        let Completion::Matches(matches) = completion else {
+            unreachable!("complete only hesitates with select");
+        };
+        matches.into_iter().next().ok_or_else(|| {
+            WdError::NoMatch {
+                query: args.input.clone(),
+            }
+            .into()
+        })
+    }
+
+    /// Going to `destination` right after a jump, when `destination` was also
+    /// a candidate for that jump's query, means the jump picked the wrong
+    /// directory. Suppress it for that query once it keeps happening.
+    fn learn_correction(
+        &self,
+        db: &mut dyn Store,
+        stats: &mut Stats,
+        from: Option<&Path>,
+        destination: &Path,
+        min_confidence: f64,
+    ) -> eyre::Result<()> {
+        let Some(from) = from else {
+            return Ok(());
+        };
+        let Some(query) = stats.leaving_last_jump(from, destination) else {
+            return Ok(());
+        };
+        let entries = db.entries()?;
+        let was_candidate = rank(&entries, query, min_confidence, &self.config.transliterate)?
+            .iter()
+            .any(|(_, path)| *path == destination);
+        if !was_candidate {
+            return Ok(());
+        }
+
+        if let Some((query, path, count)) = stats.corrected() {
+            if count >= tune::SUPPRESS_AFTER {
+                eprintln!("{}", messages::suppressed(&path, &query));
+                db.suppress(Suppression { query, path })?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Resolve `query` to at most `limit` matches, without touching the db.
+    /// Without `db`, it's only opened if the matches aren't cached.
+    fn lookup(
+        &self,
+        db: Option<&dyn Store>,
+        query: &Query,
+        limit: usize,
+    ) -> eyre::Result<Vec<CompleteResult>> {
+        let input = query.input.as_str();
         let input_path = Path::new(input);
         if input_path.is_dir() {
             if self.debug {
                 println!("input is concrete path");
             }
-            db.bump(input_path.canonicalize()?)?
-                .write()
-                .expect("failed to write to db");
-            return Ok(vec![CompleteResult::new(1.0, input_path.canonicalize()?)]);
+            return Ok(vec![CompleteResult::new(
+                1.0,
+                input_path.canonicalize()?,
+                Origin::Filesystem,
+            )]);
         }
 
-        let mut paths: Vec<(f64, &PathBuf)> = db
-            .paths()
-            .iter()
-            .enumerate()
-            .map(|(i, path)| (dist(path, input).unwrap() * weight(i), path))
-            .filter(|(confidence, _)| *confidence > min_confidence)
-            .collect();
+        // temporal boosts change with the time, not only with the db
+        let slot = self.temporal_context().map(|(slot, _)| slot);
+        let key = format!(
+            "{}\0{slot:?}\0{:?}\0{}\0{}\0{limit}\0{input}",
+            query.exact,
+            query.exclude,
+            query.min_confidence,
+            query
+                .from
+                .as_deref()
+                .unwrap_or_else(|| Path::new(""))
+                .display(),
+        );
+        let mut cache = self.open_query_cache();
+        if let Some(matches) = cache.get(&key) {
+            return Ok(matches);
+        }
+        let opened;
+        let db = match db {
+            Some(db) => db,
+            None => {
+                opened = self.open_store()?;
+                opened.as_ref()
+            }
+        };
+        let matches = self.rank_sources(db, query, limit)?;
+        cache.put(key, &matches);
+        if let Err(err) = cache.write() {
+            eprintln!("{}", messages::couldnt_write("query cache", &err));
+        }
+        Ok(matches)
+    }
 
-        if paths.is_empty() {
-            return Ok(vec![]);
+    /// The current time of the day and week and the local UTC offset, if
+    /// temporal boosts are enabled.
+    fn temporal_context(&self) -> Option<(temporal::Slot, i64)> {
+        if !self.config.temporal.enabled {
+            return None;
         }
+        let offset = temporal::local_offset(&self.config.temporal);
+        Some((temporal::Slot::of(store::now(), offset), offset))
+    }
+
+    /// Matches for `query` from every enabled source, best first.
+    fn rank_sources(
+        &self,
+        db: &dyn Store,
+        query: &Query,
+        limit: usize,
+    ) -> eyre::Result<Vec<CompleteResult>> {
+        self.score_sources(db, query, limit, None)
+    }
 
-        paths.sort_by(|(weight1, _), (weight2, _)| weight2.partial_cmp(weight1).unwrap());
-        let matches: Vec<_> = paths
+    /// Hand up to `limit` matches for `query` to `emit` as they're scored,
+    /// source by source in their own order rather than best first, for
+    /// pickers that sort on their own. Exact and fused matching need every
+    /// score before ranking anything, they're emitted once sorted.
+    fn stream_sources(
+        &self,
+        db: &dyn Store,
+        query: &Query,
+        limit: usize,
+        emit: &mut dyn FnMut(CompleteResult) -> eyre::Result<()>,
+    ) -> eyre::Result<()> {
+        if query.exact || self.config.fusion.enabled {
+            for m in self.rank_sources(db, query, limit)? {
+                emit(m)?;
+            }
+            return Ok(());
+        }
+        self.score_sources(db, query, limit, Some(emit))?;
+        Ok(())
+    }
+
+    /// Matches for `query` from every enabled source, best first, or handed
+    /// to `stream` unsorted as they come.
+    fn score_sources(
+        &self,
+        db: &dyn Store,
+        query: &Query,
+        limit: usize,
+        mut stream: Option<&mut dyn FnMut(CompleteResult) -> eyre::Result<()>>,
+    ) -> eyre::Result<Vec<CompleteResult>> {
+        let input = query.input.as_str();
+        let suppressed: Vec<PathBuf> = db
+            .suppressions()?
             .into_iter()
-            .map(|(confidence, path)| CompleteResult::new(confidence, path.clone()))
-            .take(list.unwrap_or(1))
+            .filter(|s| s.query == input)
+            .map(|s| s.path)
+            .chain(db.snoozed()?.into_iter().map(|s| s.path))
             .collect();
+        let transitions = match &query.from {
+            Some(from) => db.transitions_from(from)?,
+            None => vec![],
+        };
+
+        let affinity = match self.temporal_context() {
+            Some((slot, offset)) => temporal::affinity(&db.jumps()?, slot, offset),
+            None => HashMap::new(),
+        };
+
+        // leave room for candidates the context could push over the threshold
+        let mut room = 1.;
+        if !transitions.is_empty() {
+            room *= 1. - TRANSITION_BOOST;
+        }
+        if !affinity.is_empty() {
+            room *= 1. - self.config.temporal.boost;
+        }
+        let min_confidence = (query.min_confidence - (1. - room)) / room;
+
+        // exact matches are scored by position, not similarity
+        let scale = if query.exact {
+            1.
+        } else {
+            matcher::calibration(
+                input.chars().count(),
+                self.config
+                    .calibration
+                    .as_deref()
+                    .unwrap_or(matcher::DEFAULT_CALIBRATION),
+            )
+        };
+
+        let host = self.host();
+        let ctx = sources::Context {
+            db,
+            from: query.from.as_deref(),
+            host: host.as_deref(),
+        };
+        let mut matches: Vec<CompleteResult> = vec![];
+        let mut streamed: HashSet<PathBuf> = HashSet::new();
+        let repos_cache = (!self.no_cache && !self.read_only)
+            .then(|| store::sidecar_path(self.db_path.as_deref(), &self.config, "repos"));
+        for (source, source_weight) in sources::enabled(&self.config.sources, repos_cache) {
+            if source_weight <= 0. {
+                continue;
+            }
+            let entries = source.candidates(&ctx)?;
+            let weights = if source.by_recency() {
+                matcher::weight
+            } else {
+                matcher::flat
+            };
+            let threshold = min_confidence / (source_weight * scale);
+            let boosted = |confidence: f64, path: &PathBuf| {
+                if suppressed.contains(path) || self.is_private(path) {
+                    return None;
+                }
+                let confidence = matcher::boost_transitions(
+                    (confidence * source_weight * scale).min(1.),
+                    path,
+                    &transitions,
+                );
+                let confidence = matcher::boost_temporal(
+                    confidence,
+                    path,
+                    &affinity,
+                    self.config.temporal.boost,
+                );
+                (confidence > query.min_confidence).then_some(confidence)
+            };
+
+            if let Some(emit) = stream.as_mut() {
+                matcher::stream_weighted(
+                    &entries,
+                    input,
+                    threshold,
+                    weights,
+                    &self.config.transliterate,
+                    |confidence, path| {
+                        let Some(confidence) = boosted(confidence, path) else {
+                            return Ok(true);
+                        };
+                        // sources overlap, the first one to find a directory
+                        // gets to show it
+                        if query.exclude.contains(path)
+                            || streamed.contains(path)
+                            || (self.config.inaccessible == Inaccessible::Hide
+                                && !access::can_enter(path))
+                        {
+                            return Ok(true);
+                        }
+                        streamed.insert(path.clone());
+                        emit(CompleteResult::new(
+                            confidence,
+                            path.clone(),
+                            Origin::Source(source.name()),
+                        ))?;
+                        Ok(streamed.len() < limit)
+                    },
+                )?;
+                if streamed.len() >= limit {
+                    break;
+                }
+                continue;
+            }
+
+            let ranked = if query.exact {
+                matcher::rank_exact(
+                    &entries,
+                    input,
+                    threshold,
+                    weights,
+                    &self.config.transliterate,
+                )
+            } else if self.config.fusion.enabled {
+                matcher::rank_fused(
+                    &entries,
+                    input,
+                    threshold,
+                    weights,
+                    &self.config.transliterate,
+                    &self.config.fusion,
+                )?
+            } else {
+                matcher::rank_weighted(
+                    &entries,
+                    input,
+                    threshold,
+                    weights,
+                    &self.config.transliterate,
+                )?
+            };
 
-        if list.is_none() {
-            if let Some(item) = matches.first() {
-                db.bump(item.path.clone())?.write()?;
+            for (confidence, path) in ranked {
+                let Some(confidence) = boosted(confidence, path) else {
+                    continue;
+                };
+                // the same directory from several sources keeps its best score
+                match matches.iter_mut().find(|m| m.path == *path) {
+                    Some(m) if m.confidence >= confidence => {}
+                    Some(m) => {
+                        m.confidence = confidence;
+                        m.source = Origin::Source(source.name());
+                    }
+                    None => matches.push(CompleteResult::new(
+                        confidence,
+                        path.clone(),
+                        Origin::Source(source.name()),
+                    )),
+                }
             }
         }
-        if self.debug {
-            println!("time: {:.2} ms", now.elapsed().as_micros() as f64 / 1000.)
+        matches.retain(|m| !query.exclude.contains(&m.path));
+        let stages = &self.config.transliterate;
+        matches.sort_by(|a, b| {
+            b.confidence.total_cmp(&a.confidence).then_with(|| {
+                let key = |path: &Path| translit::collation_key(&path.to_string_lossy(), stages);
+                key(&a.path).cmp(&key(&b.path))
+            })
+        });
+        if self.config.inaccessible == Inaccessible::Hide {
+            // only as many checks as it takes to fill the limit
+            let mut kept = 0;
+            matches.retain(|m| {
+                let keep = kept < limit && access::can_enter(&m.path);
+                kept += usize::from(keep);
+                keep
+            });
         }
+        matches.truncate(limit);
         Ok(matches)
     }
 
-    fn forget(&self, input: Option<&str>) -> eyre::Result<()> {
-        let mut db = DB::open(self.db_path.as_deref()).wrap_err("error loading wd db")?;
+    /// Forget `input`, or only snooze it for `temporarily` seconds. Paths
+    /// that don't exist anymore are looked up in the db as given, then
+    /// normalized.
+    fn forget(&self, input: Option<&str>, temporarily: Option<u64>) -> eyre::Result<()> {
+        self.ensure_writable()?;
+        let mut db = self.open_store()?;
+
+        let input = input.map(Path::new).unwrap_or_else(|| Path::new("."));
+        let path = match input.canonicalize() {
+            Ok(path) => path,
+            Err(err) => {
+                let normalized = normalize(input)?;
+                db.entries()?
+                    .into_iter()
+                    .map(|e| e.path)
+                    .find(|path| path == input || *path == normalized)
+                    .ok_or(err)
+                    .wrap_err_with(|| messages::no_entry(input))?
+            }
+        };
+        self.forget_paths(db.as_mut(), vec![path], temporarily)?;
+        Ok(())
+    }
+
+    /// Forget, or snooze, every entry matching the glob `pattern`.
+    fn forget_pattern(&self, pattern: &str, temporarily: Option<u64>) -> eyre::Result<usize> {
+        self.ensure_writable()?;
+        let mut db = self.open_store()?;
+        let glob = GlobBuilder::new(pattern)
+            .literal_separator(true)
+            .build()
+            .wrap_err_with(|| messages::invalid_pattern(pattern))?
+            .compile_matcher();
+        let paths: Vec<PathBuf> = db
+            .entries()?
+            .into_iter()
+            .map(|e| e.path)
+            .filter(|path| glob.is_match(path))
+            .collect();
+        self.forget_paths(db.as_mut(), paths, temporarily)
+    }
+
+    fn forget_paths(
+        &self,
+        db: &mut dyn Store,
+        paths: Vec<PathBuf>,
+        temporarily: Option<u64>,
+    ) -> eyre::Result<usize> {
+        let count = paths.len();
+        if let Some(duration) = temporarily {
+            for path in paths {
+                db.snooze(path, store::now() + duration)?;
+            }
+            db.flush().wrap_err_with(messages::writing_db)?;
+            return Ok(count);
+        }
+        for path in &paths {
+            db.forget(path.clone())?;
+        }
+        db.flush().wrap_err_with(messages::writing_db)?;
+        let mut stats = self.open_stats()?;
+        for path in &paths {
+            stats.forgot(path);
+        }
+        stats.write()?;
+        Ok(count)
+    }
+
+    fn forget_interactive(
+        &self,
+        input: Option<&str>,
+        temporarily: Option<u64>,
+    ) -> eyre::Result<usize> {
+        self.ensure_writable()?;
+        let mut db = self.open_store()?;
+        let entries = db.entries()?;
+
+        let scored: Vec<(f64, &PathBuf)> = input.map_or_else(
+            || {
+                Ok(entries
+                    .iter()
+                    .enumerate()
+                    .map(|(i, entry)| (weight(i), &entry.path))
+                    .collect())
+            },
+            |input| {
+                rank(
+                    &entries,
+                    input,
+                    f64::NEG_INFINITY,
+                    &self.config.transliterate,
+                )
+            },
+        )?;
+        if scored.is_empty() {
+            return Ok(0);
+        }
+
+        let mut cache = self.open_existence_cache()?;
+        let color = self.color.enabled(&std::io::stderr());
+        let items: Vec<_> = scored
+            .iter()
+            .map(|(score, path)| {
+                let exists = cache.is_dir(path);
+                let shown = output::escape(path).into_owned();
+                let label = format!(
+                    "[{score:.2}] {} {}",
+                    if exists { " " } else { "✗" },
+                    match input {
+                        Some(input) if color => highlight::paint(&shown, input),
+                        _ => shown,
+                    }
+                );
+                // dead entries are what a cleanup is usually after
+                (label, !exists)
+            })
+            .collect();
+        cache.write()?;
+        let selected = MultiSelect::new()
+            .with_prompt("entries to forget (space to select, enter to confirm)")
+            .items_checked(items)
+            .max_length(20)
+            .interact_opt()?
+            .unwrap_or_default();
+
+        for &i in &selected {
+            let path = scored[i].1.clone();
+            match temporarily {
+                Some(duration) => db.snooze(path, store::now() + duration)?,
+                None => db.forget(path)?,
+            }
+        }
+        db.flush().wrap_err_with(messages::writing_db)?;
+        Ok(selected.len())
+    }
+
+    /// Print the jump log, most recent first, only keeping jumps to
+    /// directories matching `query` if given.
+    fn history(&self, query: Option<&str>, limit: usize) -> eyre::Result<()> {
+        let db = self.open_store()?;
+        let jumps = db.jumps()?;
+        let matching: Option<Vec<PathBuf>> = match query {
+            Some(query) => {
+                let mut paths: Vec<Entry> = vec![];
+                for jump in &jumps {
+                    if !paths.iter().any(|e| e.path == jump.path) {
+                        paths.push(sources::unvisited(jump.path.clone()));
+                    }
+                }
+                let ranked = matcher::rank_weighted(
+                    &paths,
+                    query,
+                    self.confidence(None),
+                    matcher::flat,
+                    &self.config.transliterate,
+                )?;
+                Some(ranked.into_iter().map(|(_, path)| path.clone()).collect())
+            }
+            None => None,
+        };
+
+        let now = store::now();
+        let out = self.output();
+        for jump in jumps
+            .iter()
+            .rev()
+            .filter(|j| !self.is_private(&j.path))
+            .filter(|j| matching.as_ref().is_none_or(|m| m.contains(&j.path)))
+            .take(limit)
+        {
+            out.record(&format!(
+                "{}  {:>4} ago  {}",
+                time::format_timestamp(jump.at),
+                time::format_duration(now.saturating_sub(jump.at)),
+                out.show(&jump.path)
+            ))?;
+        }
+        Ok(())
+    }
+
+    fn status(&self, path: Option<&Path>, porcelain: bool) -> eyre::Result<()> {
+        let path = path
+            .map_or_else(std::env::current_dir, Path::canonicalize)
+            .wrap_err_with(messages::couldnt_find_dir)?;
+        let db = self.open_store()?;
+        let entry = db
+            .entries()?
+            .into_iter()
+            .enumerate()
+            .find(|(_, e)| e.path == path);
+        let host = self.host();
+        let dormant = entry.as_ref().is_some_and(|(_, e)| {
+            host.as_ref()
+                .is_some_and(|host| !e.hosts.is_empty() && !e.hosts.contains(host))
+        });
+        let status = status::Status {
+            path: &path,
+            pinned: self
+                .config
+                .sources
+                .bookmarks
+                .paths
+                .iter()
+                .any(|p| p.canonicalize().is_ok_and(|p| p == path)),
+            snoozed: db.snoozed()?.iter().any(|s| s.path == path),
+            dormant,
+            entry,
+        };
+        if porcelain {
+            println!("{}", status.porcelain());
+        } else {
+            println!("{}", status.human());
+        }
+        Ok(())
+    }
+
+    fn list(&self, copy: bool) -> eyre::Result<()> {
+        let db = self.open_store()?;
+        if copy {
+            let paths: Vec<String> = db
+                .entries()?
+                .iter()
+                .filter(|e| !self.is_private(&e.path))
+                .map(|e| e.path.display().to_string())
+                .collect();
+            clipboard::copy(&paths.join("\n"))?;
+            eprintln!("{}", messages::copied_count(paths.len()));
+            return Ok(());
+        }
+        let snoozed = db.snoozed()?;
+        let host = self.host();
+        let now = store::now();
+        let out = self.output();
+        for entry in db.entries()? {
+            if self.is_private(&entry.path) {
+                continue;
+            }
+            let dormant = host
+                .as_ref()
+                .is_some_and(|host| !entry.hosts.is_empty() && !entry.hosts.contains(host));
+            match snoozed.iter().find(|s| s.path == entry.path) {
+                Some(s) => out.record(&format!(
+                    "{} (snoozed for {})",
+                    out.show(&entry.path),
+                    time::format_duration(s.until.saturating_sub(now))
+                ))?,
+                None if dormant => out.record(&format!(
+                    "{} (only visited on {})",
+                    out.show(&entry.path),
+                    entry.hosts.join(", ")
+                ))?,
+                None => out.path(&entry.path)?,
+            }
+        }
+        Ok(())
+    }
+
+    fn tune(&self, apply: bool) -> eyre::Result<()> {
+        let stats = self.open_stats()?;
+        let current = self.confidence(None);
+        stats.print_histogram();
+        let Some(suggested) = stats.suggest(current) else {
+            println!(
+                "not enough data yet ({} samples), keep using wd for a while",
+                stats.samples()
+            );
+            return Ok(());
+        };
+        println!("current confidence: {current:.2}, suggested: {suggested:.2}");
+        if apply {
+            self.ensure_writable()?;
+            let path = Config::path(self.config_path.as_deref())
+                .ok_or_else(|| eyre::eyre!(messages::no_config_path()))?;
+            let profile = self.config.profile.as_deref();
+            Config::set(&path, profile, "confidence", suggested)?;
+            match profile {
+                Some(profile) => println!(
+                    "wrote confidence = {suggested:.2} for profile {profile} to {}",
+                    path.display()
+                ),
+                None => println!("wrote confidence = {suggested:.2} to {}", path.display()),
+            }
+        }
+        Ok(())
+    }
+
+    fn migrate_home(&self, old: &Path, new: &Path, host: Option<&str>) -> eyre::Result<()> {
+        self.ensure_writable()?;
+        let hosts = host
+            .map(|host| {
+                host.split_once('=')
+                    .ok_or_else(|| eyre::eyre!(messages::bad_host_rename(host)))
+            })
+            .transpose()?;
+        let mut db = self.open_store()?;
+        let mut content = db.export()?;
+        let moved = migrate::move_home(&mut content, old, new);
+        println!("moved {moved} entries to {}", new.display());
+        if let Some((old, new)) = hosts {
+            let renamed = migrate::rename_host(&mut content, old, new);
+            println!("renamed host {old} to {new} on {renamed} entries");
+        }
+        self.import(db.as_mut(), content)?;
+        db.flush().wrap_err_with(messages::writing_db)
+    }
+
+    /// Learn a jump to `path`, picked outside of wd.
+    fn track(&self, path: &Path, from: Option<&Path>) -> eyre::Result<()> {
+        self.ensure_writable()?;
+        let path = path
+            .canonicalize()
+            .wrap_err_with(|| messages::couldnt_find(path))?;
+        let query = Query {
+            input: path.display().to_string(),
+            min_confidence: self.confidence(None),
+            exact: false,
+            from: from
+                .map(Path::to_path_buf)
+                .or_else(|| std::env::current_dir().ok())
+                .and_then(|from| from.canonicalize().ok()),
+            exclude: Exclusions::default(),
+        };
+        let mut db = self.open_store()?;
+        let mut stats = self.open_stats().unwrap_or_else(|err| {
+            eprintln!("{}", messages::ignoring_unreadable("stats", &err));
+            Stats::default()
+        });
+        let item = CompleteResult::new(1., path, Origin::Filesystem);
+        self.learn(db.as_mut(), &mut stats, &query, Some(&item), None)
+    }
+
+    /// Point at `migrate-home` if the history looks like it's from another
+    /// home directory.
+    fn hint_old_home(&self) {
+        let Some(home) = dirs::home_dir() else {
+            return;
+        };
+        let Ok(db) = self.open_store() else {
+            return;
+        };
+        let Ok(entries) = db.entries() else {
+            return;
+        };
+        if let Some(old) = migrate::detect_old_home(&entries, &home) {
+            eprintln!("{}", messages::old_home(&old, &home));
+        }
+    }
+
+    fn seed_projects(
+        &self,
+        roots: &[PathBuf],
+        depth: usize,
+        markers: &[String],
+    ) -> eyre::Result<usize> {
+        self.ensure_writable()?;
+        let markers = if markers.is_empty() {
+            projects::markers(self.config.projects.markers.as_deref())
+        } else {
+            markers.to_vec()
+        };
+        let mut found = projects::find(roots, depth, &markers);
+        found.retain(|path| !self.is_private(path));
+        let mut db = self.open_store()?;
+        let added = projects::seed(db.as_mut(), found, self.host().as_deref())?;
+        db.flush().wrap_err_with(messages::writing_db)?;
+        Ok(added)
+    }
+
+    /// Watch the parents of every entry until interrupted, updating the db
+    /// when entries are deleted or renamed.
+    fn watch(&self) -> eyre::Result<()> {
+        self.ensure_writable()?;
+        let (tx, rx) = std::sync::mpsc::channel();
+        let mut watcher =
+            notify::recommended_watcher(tx).wrap_err_with(messages::couldnt_start_watching)?;
+        let mut watched = BTreeSet::new();
+        let mut entries = vec![];
+        let mut refresh = true;
+        // moved away, until the rename's other end shows up or it's too late
+        let mut pending: Option<PathBuf> = None;
+        loop {
+            if refresh {
+                entries = self.open_store()?.entries()?;
+                let parents = watch::parents(&entries);
+                for dir in watched.difference(&parents) {
+                    watcher.unwatch(dir).ok();
+                }
+                for dir in parents.difference(&watched) {
+                    if let Err(err) = watcher.watch(dir, notify::RecursiveMode::NonRecursive) {
+                        eprintln!("{}", messages::couldnt_watch(dir, &err));
+                    }
+                }
+                watched = parents;
+            }
+            let timeout = if pending.is_some() {
+                Duration::from_millis(watch::RENAME_WAIT_MS)
+            } else {
+                Duration::from_secs(watch::REFRESH_SECS)
+            };
+            match rx.recv_timeout(timeout) {
+                Ok(event) => {
+                    let change = event.map(|event| watch::change(&event))?;
+                    refresh = match change {
+                        Some(Change::MovedAway(path)) => match pending.replace(path) {
+                            Some(previous) => self.follow(Change::Removed(previous), &entries)?,
+                            None => false,
+                        },
+                        Some(change) => {
+                            if matches!(&change, Change::Renamed { from, .. } if pending.as_ref() == Some(from))
+                            {
+                                pending = None;
+                            }
+                            self.follow(change, &entries)?
+                        }
+                        None => false,
+                    };
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    refresh = match pending.take() {
+                        Some(path) => self.follow(Change::Removed(path), &entries)?,
+                        None => true,
+                    };
+                }
+                Err(RecvTimeoutError::Disconnected) => return Ok(()),
+            }
+        }
+    }
+
+    /// Apply `change` to the db if it concerns any of `entries`, returning
+    /// whether it did.
+    fn follow(&self, change: Change, entries: &[Entry]) -> eyre::Result<bool> {
+        match change {
+            Change::MovedAway(_) => return Ok(false),
+            Change::Removed(path) => {
+                let gone: Vec<PathBuf> = entries
+                    .iter()
+                    .map(|e| e.path.clone())
+                    .filter(|p| p.starts_with(&path) && !p.exists())
+                    .collect();
+                if gone.is_empty() {
+                    return Ok(false);
+                }
+                let mut db = self.open_store()?;
+                let count = self.forget_paths(db.as_mut(), gone, None)?;
+                println!("{} deleted, forgot {count} entries", path.display());
+            }
+            Change::Renamed { from, to } => {
+                if !entries.iter().any(|e| e.path.starts_with(&from)) {
+                    return Ok(false);
+                }
+                let mut db = self.open_store()?;
+                let mut content = db.export()?;
+                let moved = migrate::move_home(&mut content, &from, &to);
+                self.import(db.as_mut(), content)?;
+                db.flush().wrap_err_with(messages::writing_db)?;
+                println!(
+                    "{} renamed to {}, moved {moved} entries",
+                    from.display(),
+                    to.display()
+                );
+            }
+        }
+        Ok(true)
+    }
+
+    /// Add `path` to `private_paths` in the config file and scrub it from
+    /// the db right away.
+    fn private_add(&mut self, path: &Path) -> eyre::Result<()> {
+        self.ensure_writable()?;
+        let path = normalize(path)?;
+        let path = path.canonicalize().unwrap_or(path);
+        let config = Config::path(self.config_path.as_deref())
+            .ok_or_else(|| eyre::eyre!(messages::no_config_path()))?;
+        let value = path
+            .to_str()
+            .ok_or_else(|| eyre::eyre!(messages::not_unicode(&path)))?;
+        if Config::append(&config, "private_paths", value)? {
+            println!(
+                "added {} to private_paths in {}",
+                path.display(),
+                config.display()
+            );
+        }
+        self.config.private_paths.push(path);
+        let mut db = self.open_store()?;
+        let mut stats = self.open_stats().unwrap_or_else(|err| {
+            eprintln!("{}", messages::ignoring_unreadable("stats", &err));
+            Stats::default()
+        });
+        if self.scrub_private(db.as_mut(), &mut stats)? {
+            db.flush().wrap_err_with(messages::writing_db)?;
+        }
+        // the caches may name it even if nothing under it was recorded
+        self.drop_caches();
+        Ok(())
+    }
+
+    /// Learn a jump made without `complete`, only warning if it fails since
+    /// the jump itself still happens.
+    fn record_jump(&self, path: &Path, from: Option<&Path>) {
+        if self.read_only {
+            return;
+        }
+        if let Err(err) = self.track(path, from) {
+            eprintln!("{}", messages::couldnt_record_jump(&err));
+        }
+    }
+
+    /// Learn a jump the shell made to `path`, from `input` matching it with
+    /// `score` if given. Like `record_jump`, failing only warns.
+    fn confirm(&self, path: &Path, from: Option<&Path>, input: Option<&str>, score: f64) {
+        let Some(input) = input else {
+            return self.record_jump(path, from);
+        };
+        if self.read_only {
+            return;
+        }
+        let learned = (|| {
+            let path = path
+                .canonicalize()
+                .wrap_err_with(|| messages::couldnt_find(path))?;
+            let query = Query {
+                input: input.to_owned(),
+                min_confidence: self.confidence(None),
+                exact: false,
+                from: from.and_then(|from| from.canonicalize().ok()),
+                exclude: Exclusions::default(),
+            };
+            let mut db = self.open_store()?;
+            let mut stats = self.open_stats().unwrap_or_else(|err| {
+                eprintln!("{}", messages::ignoring_unreadable("stats", &err));
+                Stats::default()
+            });
+            let item = CompleteResult::new(score, path, Origin::Source("history"));
+            self.learn(db.as_mut(), &mut stats, &query, Some(&item), None)
+        })();
+        if let Err(err) = learned {
+            eprintln!("{}", messages::couldnt_record_jump(&err));
+        }
+    }
+
+    /// `dirs` as absolute paths, the current directory if there are none.
+    fn group_dirs(dirs: &[PathBuf]) -> eyre::Result<Vec<PathBuf>> {
+        if dirs.is_empty() {
+            return Ok(vec![std::env::current_dir()?.canonicalize()?]);
+        }
+        dirs.iter()
+            .map(|dir| {
+                let dir = normalize(dir)?;
+                Ok(dir.canonicalize().unwrap_or(dir))
+            })
+            .collect()
+    }
+
+    fn group_add(&self, name: &str, dirs: &[PathBuf]) -> eyre::Result<()> {
+        self.ensure_writable()?;
+        let mut db = self.open_store()?;
+        let mut group = db
+            .groups()?
+            .into_iter()
+            .find(|g| g.name == name)
+            .unwrap_or_else(|| store::Group {
+                name: name.to_string(),
+                paths: vec![],
+                edited: 0,
+            });
+        for dir in Self::group_dirs(dirs)? {
+            if !dir.is_dir() {
+                eyre::bail!(messages::not_a_directory(&dir));
+            }
+            if self.is_private(&dir) {
+                eyre::bail!(messages::private_dir(&dir));
+            }
+            if !group.paths.contains(&dir) {
+                group.paths.push(dir);
+            }
+        }
+        db.set_group(group)?;
+        db.flush().wrap_err_with(messages::writing_db)
+    }
+
+    fn group_remove(&self, name: &str, dirs: &[PathBuf]) -> eyre::Result<()> {
+        self.ensure_writable()?;
+        let mut db = self.open_store()?;
+        let mut group = db
+            .groups()?
+            .into_iter()
+            .find(|g| g.name == name)
+            .ok_or_else(|| eyre::eyre!(messages::no_group(name)))?;
+        if dirs.is_empty() {
+            group.paths.clear();
+        } else {
+            let dirs = Self::group_dirs(dirs)?;
+            group.paths.retain(|p| !dirs.contains(p));
+        }
+        db.set_group(group)?;
+        db.flush().wrap_err_with(messages::writing_db)
+    }
+
+    fn group_list(&self, name: Option<&str>) -> eyre::Result<()> {
+        let groups = self.open_store()?.groups()?;
+        let out = self.output();
+        if let Some(name) = name {
+            let group = groups
+                .iter()
+                .find(|g| g.name == name)
+                .ok_or_else(|| eyre::eyre!(messages::no_group(name)))?;
+            for path in &group.paths {
+                out.path(path)?;
+            }
+            return Ok(());
+        }
+        for group in &groups {
+            out.record(&format!("{}:", group.name))?;
+            for path in &group.paths {
+                out.record(&format!("  {}", out.show(path)))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The directory after or before the group member `from` is in, if it's
+    /// in a group.
+    fn group_step(
+        &self,
+        from: &Path,
+        direction: groups::Direction,
+    ) -> eyre::Result<Option<PathBuf>> {
+        let groups = self.open_store()?.groups()?;
+        Ok(groups::containing(&groups, from)
+            .map(|(group, index)| groups::step(group, index, direction)))
+    }
 
-        let path = input.map(Path::new).unwrap_or_else(|| Path::new("."));
-        db.forget(path.canonicalize().wrap_err("foo")?)?.write()?;
+    /// Where a jump for `args` goes, asking the user to pick among close
+    /// candidates on the terminal, and what `confirm` should learn once
+    /// there.
+    fn jump(&self, args: &QueryArgs) -> eyre::Result<eval::Confirm> {
+        let sure = self
+            .config
+            .sure_confidence
+            .unwrap_or(DEFAULT_SURE_CONFIDENCE);
+        let query = self.query(args);
+        let matches = match self.complete(&query, None, 0, Some(sure), true)? {
+            Completion::Matches(matches) => matches,
+            Completion::Ambiguous(matches) => {
+                let ambiguous = || WdError::Ambiguous {
+                    query: args.input.clone(),
+                    count: matches.len(),
+                };
+                if !std::io::IsTerminal::is_terminal(&std::io::stderr()) {
+                    return Err(ambiguous().into());
+                }
+                let terminal = output::Output {
+                    print0: false,
+                    human: true,
+                };
+                let items: Vec<String> = matches
+                    .iter()
+                    .map(|m| self.shown(&m.path, terminal))
+                    .collect();
+                let picked = Select::new()
+                    .items(&items)
+                    .default(0)
+                    .interact_opt()?
+                    .ok_or_else(ambiguous)?;
+                return Ok(matches[picked].confirm(&query));
+            }
+        };
+        if let Some(best) = matches.first() {
+            return Ok(best.confirm(&query));
+        }
+        self.hint_old_home();
+        Err(WdError::NoMatch {
+            query: args.input.clone(),
+        }
+        .into())
+    }
 
-        db.write().wrap_err("error writing wd db")?;
+    fn db_size(&self) -> Option<u64> {
+        let path = store::db_file_path(self.db_path.as_deref(), &self.config);
+        std::fs::metadata(path).ok().map(|m| m.len())
+    }
+
+    fn db_verify(&self) -> eyre::Result<()> {
+        let content = self.open_store()?.export()?;
+        let issues = maintenance::verify(&content);
+        for issue in &issues {
+            println!("{issue}");
+        }
+        println!("{}", maintenance::summary(&content));
+        if !issues.is_empty() {
+            eyre::bail!(messages::verify_problems(issues.len()));
+        }
         Ok(())
     }
+
+    fn db_compact(&self) -> eyre::Result<()> {
+        self.ensure_writable()?;
+        let before = self.db_size();
+        let mut db = self.open_store()?;
+        let mut content = db.export()?;
+        let fixed = maintenance::compact(&mut content);
+        println!("fixed {fixed} problems");
+        println!("{}", maintenance::summary(&content));
+        self.import(db.as_mut(), content)?;
+        db.flush().wrap_err_with(messages::writing_db)?;
+        drop(db);
+        if let (Some(before), Some(after)) = (before, self.db_size()) {
+            println!("{before} bytes -> {after} bytes");
+        }
+        Ok(())
+    }
+
+    fn sync(&self, remote: &str, push: bool) -> eyre::Result<sync::SyncReport> {
+        self.ensure_writable()?;
+        let mut db = self.open_store()?;
+        let report = sync::sync(
+            db.as_mut(),
+            &sync::Remote::parse(remote),
+            push,
+            &self.config.private_paths,
+        )?;
+        db.flush().wrap_err_with(messages::writing_db)?;
+        Ok(report)
+    }
 }
 
-fn main() -> eyre::Result<()> {
-    let opts: Opts = Opts::parse();
+fn main() {
+    let code = match run() {
+        Ok(code) => code,
+        Err(err) => match err.chain().find_map(|e| e.downcast_ref::<WdError>()) {
+            Some(wd_err) if wd_err.is_outcome() => {
+                eprintln!("{wd_err}");
+                wd_err.exit_code()
+            }
+            Some(wd_err) => {
+                eprintln!("{}", messages::error(&err));
+                wd_err.exit_code()
+            }
+            None => {
+                eprintln!("{}", messages::error(&err));
+                EXIT_ERROR
+            }
+        },
+    };
+    std::process::exit(code);
+}
+
+fn run() -> eyre::Result<i32> {
+    let mut opts: Opts = Opts::parse();
+    // an empty WD_PROFILE, like one unset by direnv, is no profile
+    let profile = opts.profile.as_deref().filter(|p| !p.is_empty());
+    opts.config = Config::load(opts.config_path.as_deref(), profile)?;
+    if let Some(store) = opts.store {
+        opts.config.store = store;
+    }
 
     match &opts.action {
         Action::Complete {
+            query,
+            list,
+            max_results,
+            relative,
+            copy: true,
+            ..
+        } => {
+            let limit = list_limit(*list, *max_results).unwrap_or(1);
+            let matches = opts.lookup(None, &opts.query(query), limit)?;
+            if matches.is_empty() {
+                return Err(WdError::NoMatch {
+                    query: query.input.clone(),
+                }
+                .into());
+            }
+            let cwd = if *relative {
+                Some(std::env::current_dir()?.canonicalize()?)
+            } else {
+                None
+            };
+            let paths: Vec<String> = matches
+                .into_iter()
+                .map(|p| match &cwd {
+                    Some(cwd) => shortest_relative(&p.path, cwd),
+                    None => p.path,
+                })
+                .map(|path| path.display().to_string())
+                .collect();
+            clipboard::copy(&paths.join("\n"))?;
+            eprintln!("{}", messages::copied(&paths));
+        }
+        Action::Complete {
+            query,
+            list,
+            max_results,
+            relative,
+            stream: true,
+            ..
+        } => {
+            if opts.chdir_file.is_some() {
+                eyre::bail!(messages::chdir_file_list());
+            }
+            let limit = list_limit(*list, *max_results).unwrap_or(1);
+            let cwd = if *relative {
+                Some(std::env::current_dir()?.canonicalize()?)
+            } else {
+                None
+            };
+            let color = !opts.print0 && opts.color.enabled(&std::io::stdout());
+            let db = opts.open_store()?;
+            let mut count = 0;
+            opts.stream_sources(db.as_ref(), &opts.query(query), limit, &mut |m| {
+                opts.print_match(&m, cwd.as_deref(), color.then_some(query.input.as_str()))?;
+                // records ending with a NUL aren't flushed by line buffering
+                std::io::Write::flush(&mut std::io::stdout())?;
+                count += 1;
+                Ok(())
+            })?;
+            if count == 0 {
+                return Err(WdError::NoMatch {
+                    query: query.input.clone(),
+                }
+                .into());
+            }
+        }
+        Action::Complete {
+            query,
+            list,
+            min_results,
+            max_results,
+            relative,
+            ..
+        } => {
+            if list.is_some() && opts.chdir_file.is_some() {
+                eyre::bail!(messages::chdir_file_list());
+            }
+            let list = list_limit(*list, *max_results);
+            let completion = opts.complete(&opts.query(query), list, *min_results, None, false)?;
+            let Completion::Matches(matches) = completion else {
+                unreachable!("complete only hesitates with select");
+            };
+            if matches.is_empty() {
+                opts.hint_old_home();
+                return Err(WdError::NoMatch {
+                    query: query.input.clone(),
+                }
+                .into());
+            };
+            let cwd = if *relative {
+                Some(std::env::current_dir()?.canonicalize()?)
+            } else {
+                None
+            };
+            if let (Some(_), [best]) = (&opts.chdir_file, matches.as_slice()) {
+                let path = cwd.as_ref().map_or_else(
+                    || best.path.clone(),
+                    |cwd| shortest_relative(&best.path, cwd),
+                );
+                opts.print_dir(&path)?;
+                return Ok(EXIT_OK);
+            }
+            // only listings, the shell functions read single matches as is
+            let color = list.is_some() && !opts.print0 && opts.color.enabled(&std::io::stdout());
+            for p in &matches {
+                opts.print_match(p, cwd.as_deref(), color.then_some(query.input.as_str()))?;
+            }
+        }
+        Action::Which { query } => {
+            let best = opts.lookup(None, &opts.query(query), 1)?;
+            let best = best.first().ok_or_else(|| WdError::NoMatch {
+                query: query.input.clone(),
+            })?;
+            opts.print_dir(&best.path)?;
+        }
+        Action::Forget {
             input,
-            confidence,
+            interactive: true,
+            temporarily,
+            ..
+        } => {
+            let forgotten = opts.forget_interactive(input.as_deref(), *temporarily)?;
+            if temporarily.is_some() {
+                eprintln!("{}", messages::snoozed(forgotten));
+            } else {
+                eprintln!("{}", messages::forgot(forgotten));
+            }
+        }
+        Action::Forget {
+            pattern: Some(pattern),
+            temporarily,
+            ..
+        } => {
+            let forgotten = opts.forget_pattern(pattern, *temporarily)?;
+            if temporarily.is_some() {
+                eprintln!("{}", messages::snoozed(forgotten));
+            } else {
+                eprintln!("{}", messages::forgot(forgotten));
+            }
+        }
+        Action::Forget {
+            input, temporarily, ..
+        } => {
+            opts.forget(input.as_deref(), *temporarily)?;
+        }
+        Action::List { copy } => opts.list(*copy)?,
+        Action::Status { path, porcelain } => opts.status(path.as_deref(), *porcelain)?,
+        Action::Last { count } => opts.history(None, *count)?,
+        Action::History { query, limit } => {
+            opts.history(query.as_deref(), limit.unwrap_or(usize::MAX))?;
+        }
+        Action::Group { action } => match action {
+            GroupAction::Add { name, dirs } => opts.group_add(name, dirs)?,
+            GroupAction::Remove { name, dirs } => opts.group_remove(name, dirs)?,
+            GroupAction::List { name } => opts.group_list(name.as_deref())?,
+        },
+        Action::Next | Action::Prev => {
+            let direction = if matches!(opts.action, Action::Next) {
+                groups::Direction::Next
+            } else {
+                groups::Direction::Prev
+            };
+            let cwd = std::env::current_dir()?.canonicalize()?;
+            let path = opts
+                .group_step(&cwd, direction)?
+                .ok_or_else(|| eyre::eyre!(messages::not_in_group(&cwd)))?;
+            opts.print_dir(&path)?;
+        }
+        Action::Private {
+            action: PrivateAction::Add { path },
+        } => {
+            let path = path.clone();
+            opts.private_add(&path)?;
+        }
+        Action::Private {
+            action: PrivateAction::List,
+        } => {
+            for path in &opts.config.private_paths {
+                opts.output().path(path)?;
+            }
+        }
+        Action::Db { action } => match action {
+            DbAction::Verify => opts.db_verify()?,
+            DbAction::Compact => opts.db_compact()?,
+        },
+        Action::Sync { remote, no_push } => {
+            let report = opts.sync(remote, !no_push)?;
+            println!(
+                "synced with {remote}: {} added, {} updated, {} removed",
+                report.added, report.updated, report.removed
+            );
+        }
+        Action::MigrateHome {
+            old_home,
+            new_home,
+            host,
+        } => opts.migrate_home(old_home, new_home, host.as_deref())?,
+        Action::Projects {
+            scan,
+            depth,
+            markers,
+        } => {
+            let added = opts.seed_projects(scan, *depth, markers)?;
+            println!("added {added} projects");
+        }
+        Action::Plumbing {
+            query,
             list,
+            protocol,
         } => {
-            let matches = opts.complete(input, *confidence, *list)?;
+            let list = list_limit(Some(*list), None).unwrap_or(1);
+            let matches = opts.lookup(None, &opts.query(query), list)?;
+            let mut cache = opts.open_existence_cache().unwrap_or_else(|err| {
+                eprintln!("{}", messages::ignoring_unreadable("existence cache", &err));
+                ExistenceCache::disabled()
+            });
+            plumbing::print(*protocol, &matches, &mut cache, opts.print0)?;
+            if let Err(err) = cache.write() {
+                eprintln!("{}", messages::couldnt_write("existence cache", &err));
+            }
             if matches.is_empty() {
-                eprint!("no match found for {input}");
-                std::process::exit(1);
+                return Ok(EXIT_NO_MATCH);
+            }
+        }
+        Action::CompleteDirs { word, list } => {
+            if word.is_empty() {
+                return Ok(EXIT_OK);
+            }
+            let cwd = std::env::current_dir()?;
+            let from = cwd.canonicalize().ok();
+            let query = Query {
+                input: word.clone(),
+                min_confidence: opts.confidence(None),
+                exact: false,
+                exclude: opts.exclusions(from.as_deref(), false, false, &[]),
+                from,
             };
-            for p in matches {
-                if opts.debug {
-                    println!("[{:.2}] {}", p.confidence, p.path.display());
-                } else {
-                    println!("{}", p.path.display());
+            let cwd = query.from.clone().unwrap_or(cwd);
+            let matches = opts.lookup(None, &query, *list)?;
+            for m in matches
+                .iter()
+                .filter(|m| !plumbing::offered_by_shell(word, &cwd, &m.path))
+            {
+                opts.output().path(&m.path)?;
+            }
+        }
+        Action::Track { path, from } => opts.track(path, from.as_deref())?,
+        Action::Confirm {
+            path,
+            from,
+            query,
+            score,
+        } => opts.confirm(path, from.as_deref(), query.as_deref(), *score),
+        Action::Jump { query, shell } => {
+            // `wd next` and `wd prev`, unless there's a directory by that name
+            let step = match groups::Direction::parse(&query.input) {
+                Some(direction) if !Path::new(&query.input).is_dir() => {
+                    let from = opts.query(query).from;
+                    match &from {
+                        Some(from) => opts.group_step(from, direction)?,
+                        None => None,
+                    }
+                    .map(|path| (path, from))
                 }
+                _ => None,
+            };
+            let confirm = match step {
+                Some((path, from)) => eval::Confirm {
+                    path,
+                    from,
+                    query: None,
+                },
+                None => opts.jump(query)?,
+            };
+            let path = confirm.path.clone();
+            let mut steps = vec![eval::Step::Cd(path.clone())];
+            if !opts.read_only {
+                steps.push(eval::Step::Confirm(confirm));
             }
+            if let Some(hook) = &opts.config.on_jump {
+                steps.push(eval::Step::Run(hooks::render(hook, &path, *shell)?));
+            }
+            print!("{}", eval::render(&steps, *shell)?);
         }
-        Action::Forget { input } => {
-            opts.forget(input.as_deref())?;
+        Action::ExecHooks { path, shell } => {
+            if let Some(hook) = &opts.config.on_jump {
+                let path = match path {
+                    Some(path) => path.canonicalize()?,
+                    None => std::env::current_dir()?,
+                };
+                println!("{}", hooks::render(hook, &path, *shell)?);
+            }
+        }
+        Action::Init { shell } => {
+            print!("{}", shell.script());
+        }
+        Action::GenerateAssets { out } => {
+            for path in assets::generate(Opts::command(), out)? {
+                println!("{}", path.display());
+            }
+        }
+        Action::SelfUpdate { check } => {
+            let current = env!("CARGO_PKG_VERSION");
+            let release = update::latest()?;
+            if !update::is_newer(release.version(), current) {
+                println!("wd {current} is up to date");
+            } else if *check {
+                println!("wd {} is available (current: {current})", release.version());
+            } else {
+                let exe = update::install(&release)?;
+                println!("updated {} to wd {}", exe.display(), release.version());
+            }
+        }
+        Action::Open { query, program } => {
+            let target = opts.resolve(query)?;
+            let program = program
+                .as_deref()
+                .or(opts.config.open.default.as_deref())
+                .unwrap_or("editor");
+            open::launch(program, &opts.config.open.programs, &target.path)?;
+        }
+        Action::Session { query } => {
+            let target = opts.resolve(query)?;
+            session::open(&target.path)?;
+        }
+        Action::Watch => opts.watch()?,
+        Action::Tune { apply } => {
+            opts.tune(*apply)?;
+        }
+        Action::Bench {
+            input,
+            size,
+            iterations,
+            confidence,
+        } => {
+            bench::run(input, *size, *iterations, *confidence)?;
+        }
+        #[cfg(feature = "simulate")]
+        Action::Simulate {
+            trace,
+            seed,
+            misses,
+        } => {
+            let trace = simulate::Trace::load(trace)?;
+            let mut db = match seed {
+                Some(seed) => store::MemoryStore::seeded_from(seed)?,
+                None => store::MemoryStore::default(),
+            };
+            let report = simulate::replay(&trace, &mut db, |db, step| {
+                let query = Query {
+                    input: step.query.clone(),
+                    min_confidence: opts.confidence(None),
+                    exact: false,
+                    exclude: opts.exclusions(step.from.as_deref(), false, false, &[]),
+                    from: step.from.clone(),
+                };
+                Ok(opts
+                    .rank_sources(db, &query, usize::MAX)?
+                    .into_iter()
+                    .map(|m| m.path)
+                    .collect())
+            })?;
+            println!("{}", report.summary());
+            if *misses {
+                for (query, target, rank) in &report.misses {
+                    let rank = rank.map_or_else(|| "-".to_string(), |r| (r + 1).to_string());
+                    println!("{rank}\t{query}\t{}", target.display());
+                }
+            }
         }
     };
-    Ok(())
+    Ok(EXIT_OK)
 }
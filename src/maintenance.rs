@@ -0,0 +1,274 @@
+//! Checking and cleaning up the database: `wdbin db verify` reports what's
+//! wrong with the stored content, `wdbin db compact` fixes it and rewrites
+//! the database. Entries whose directory is gone but whose device and inode
+//! turn up nearby were renamed, and follow their directory.
+
+use crate::migrate;
+use crate::store::{now, DBContent, Entry, FileId, Transition, JUMP_LOG_SIZE};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Something wrong with the stored content, all fixed by `compact`.
+#[derive(Debug)]
+pub enum Issue {
+    NotUtf8(PathBuf),
+    Relative(PathBuf),
+    DuplicateEntry(PathBuf),
+    /// fewer visits or an older last access than the jump log shows
+    StaleScore(PathBuf),
+    DuplicateTransition {
+        from: PathBuf,
+        to: PathBuf,
+    },
+    DuplicateSuppression(PathBuf),
+    ExpiredSnooze(PathBuf),
+    /// the jump log is out of order or longer than `JUMP_LOG_SIZE`
+    JumpLog,
+    /// the directory was renamed, the entry still has the old path
+    Moved {
+        from: PathBuf,
+        to: PathBuf,
+    },
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NotUtf8(path) => write!(f, "path isn't valid UTF-8: {}", path.display()),
+            Self::Relative(path) => write!(f, "relative path: {}", path.display()),
+            Self::DuplicateEntry(path) => write!(f, "duplicate entry: {}", path.display()),
+            Self::StaleScore(path) => {
+                write!(f, "visits behind the jump log: {}", path.display())
+            }
+            Self::DuplicateTransition { from, to } => write!(
+                f,
+                "duplicate transition: {} -> {}",
+                from.display(),
+                to.display()
+            ),
+            Self::DuplicateSuppression(path) => {
+                write!(f, "duplicate suppression: {}", path.display())
+            }
+            Self::ExpiredSnooze(path) => write!(f, "expired snooze: {}", path.display()),
+            Self::JumpLog => write!(f, "jump log out of order or too long"),
+            Self::Moved { from, to } => {
+                write!(f, "renamed: {} -> {}", from.display(), to.display())
+            }
+        }
+    }
+}
+
+/// Paths wd can't use: not UTF-8 or not absolute.
+fn invalid(path: &Path) -> Option<Issue> {
+    if path.to_str().is_none() {
+        Some(Issue::NotUtf8(path.to_path_buf()))
+    } else if !path.is_absolute() {
+        Some(Issue::Relative(path.to_path_buf()))
+    } else {
+        None
+    }
+}
+
+/// Visits and last access of each path according to the jump log.
+fn jump_scores(content: &DBContent) -> HashMap<&PathBuf, (u64, u64)> {
+    let mut scores: HashMap<&PathBuf, (u64, u64)> = HashMap::new();
+    for jump in &content.jumps {
+        let (visits, last) = scores.entry(&jump.path).or_default();
+        *visits += 1;
+        *last = (*last).max(jump.at);
+    }
+    scores
+}
+
+/// How far below the closest existing ancestor of a dead entry its
+/// directory is looked for, past the depth it used to be at.
+const MOVED_SEARCH_DEPTH: usize = 1;
+
+/// A directory under `dir`, at most `depth` levels down, with identity `id`.
+fn find_id(dir: &Path, id: FileId, depth: usize) -> Option<PathBuf> {
+    let mut subdirs = vec![];
+    for child in std::fs::read_dir(dir).ok()?.flatten() {
+        let is_dir = child.file_type().is_ok_and(|t| t.is_dir());
+        if !is_dir {
+            continue;
+        }
+        let path = child.path();
+        if FileId::of(&path) == Some(id) {
+            return Some(path);
+        }
+        subdirs.push(path);
+    }
+    if depth <= 1 {
+        return None;
+    }
+    subdirs
+        .iter()
+        .find_map(|subdir| find_id(subdir, id, depth - 1))
+}
+
+/// Entries whose directory doesn't exist anymore but was found elsewhere
+/// under its closest remaining ancestor, as (old path, new path).
+pub fn find_moved(content: &DBContent) -> Vec<(PathBuf, PathBuf)> {
+    content
+        .paths
+        .iter()
+        .filter(|e| !e.path.exists())
+        .filter_map(|e| {
+            let id = e.id?;
+            let ancestor = e.path.ancestors().skip(1).find(|a| a.is_dir())?;
+            let depth = e.path.components().count() - ancestor.components().count();
+            let to = find_id(ancestor, id, depth + MOVED_SEARCH_DEPTH)?;
+            Some((e.path.clone(), to))
+        })
+        .collect()
+}
+
+pub fn verify(content: &DBContent) -> Vec<Issue> {
+    let mut issues: Vec<Issue> = find_moved(content)
+        .into_iter()
+        .map(|(from, to)| Issue::Moved { from, to })
+        .collect();
+    let mut seen = HashMap::new();
+    let scores = jump_scores(content);
+    for entry in &content.paths {
+        issues.extend(invalid(&entry.path));
+        if seen.insert(&entry.path, ()).is_some() {
+            issues.push(Issue::DuplicateEntry(entry.path.clone()));
+        }
+        if let Some(&(visits, last)) = scores.get(&entry.path) {
+            if entry.visits < visits || entry.last_access < last {
+                issues.push(Issue::StaleScore(entry.path.clone()));
+            }
+        }
+    }
+    let mut seen = HashMap::new();
+    for t in &content.transitions {
+        if seen.insert((&t.from, &t.to), ()).is_some() {
+            issues.push(Issue::DuplicateTransition {
+                from: t.from.clone(),
+                to: t.to.clone(),
+            });
+        }
+    }
+    let mut seen = HashMap::new();
+    for s in &content.suppressed {
+        if seen.insert(s, ()).is_some() {
+            issues.push(Issue::DuplicateSuppression(s.path.clone()));
+        }
+    }
+    let now = now();
+    for s in &content.snoozed {
+        if s.until <= now {
+            issues.push(Issue::ExpiredSnooze(s.path.clone()));
+        }
+    }
+    let sorted = content.jumps.windows(2).all(|w| w[0].at <= w[1].at);
+    if !sorted || content.jumps.len() > JUMP_LOG_SIZE {
+        issues.push(Issue::JumpLog);
+    }
+    issues
+}
+
+/// Merge entries stored under the same path, keeping the most recent access,
+/// the sum of the visits and every host. Returns how many were merged away.
+pub fn merge_entries(content: &mut DBContent) -> usize {
+    let before = content.paths.len();
+    let mut merged: Vec<Entry> = Vec::with_capacity(content.paths.len());
+    let mut index: HashMap<PathBuf, usize> = HashMap::new();
+    for entry in content.paths.drain(..) {
+        match index.get(&entry.path) {
+            Some(&i) => {
+                let kept = &mut merged[i];
+                kept.last_access = kept.last_access.max(entry.last_access);
+                kept.visits += entry.visits;
+                for host in entry.hosts {
+                    if !kept.hosts.contains(&host) {
+                        kept.hosts.push(host);
+                    }
+                }
+            }
+            None => {
+                index.insert(entry.path.clone(), merged.len());
+                merged.push(entry);
+            }
+        }
+    }
+    content.paths = merged;
+    before - content.paths.len()
+}
+
+/// Merge transitions between the same directories, adding their counts.
+/// Returns how many were merged away.
+pub fn merge_transitions(content: &mut DBContent) -> usize {
+    let before = content.transitions.len();
+    let mut index: HashMap<(PathBuf, PathBuf), usize> = HashMap::new();
+    let mut transitions = Vec::with_capacity(content.transitions.len());
+    for t in content.transitions.drain(..) {
+        match index.get(&(t.from.clone(), t.to.clone())) {
+            Some(&i) => {
+                let kept: &mut Transition = &mut transitions[i];
+                kept.count += t.count;
+            }
+            None => {
+                index.insert((t.from.clone(), t.to.clone()), transitions.len());
+                transitions.push(t);
+            }
+        }
+    }
+    content.transitions = transitions;
+    before - content.transitions.len()
+}
+
+/// Fix every issue `verify` would report. Entries wd can't use are dropped,
+/// everything else is merged or recomputed. Returns how many issues were
+/// fixed.
+pub fn compact(content: &mut DBContent) -> usize {
+    let fixed = verify(content).len();
+
+    for (from, to) in find_moved(content) {
+        migrate::move_home(content, &from, &to);
+    }
+    content.paths.retain(|e| invalid(&e.path).is_none());
+    content.forgotten.retain(|t| invalid(&t.path).is_none());
+    content.jumps.retain(|j| invalid(&j.path).is_none());
+    content
+        .transitions
+        .retain(|t| invalid(&t.from).is_none() && invalid(&t.to).is_none());
+    merge_entries(content);
+    merge_transitions(content);
+
+    content.jumps.sort();
+    if content.jumps.len() > JUMP_LOG_SIZE {
+        content.jumps.drain(..content.jumps.len() - JUMP_LOG_SIZE);
+    }
+    let scores: HashMap<PathBuf, (u64, u64)> = jump_scores(content)
+        .into_iter()
+        .map(|(path, score)| (path.clone(), score))
+        .collect();
+    for entry in &mut content.paths {
+        if let Some(&(visits, last)) = scores.get(&entry.path) {
+            entry.visits = entry.visits.max(visits);
+            entry.last_access = entry.last_access.max(last);
+        }
+    }
+
+    content.suppressed.sort();
+    content.suppressed.dedup();
+    let now = now();
+    content.snoozed.retain(|s| s.until > now);
+    fixed
+}
+
+/// What the database holds, for the report after a check or a compaction.
+pub fn summary(content: &DBContent) -> String {
+    format!(
+        "{} entries, {} jumps, {} transitions, {} forgotten, {} suppressed, {} snoozed",
+        content.paths.len(),
+        content.jumps.len(),
+        content.transitions.len(),
+        content.forgotten.len(),
+        content.suppressed.len(),
+        content.snoozed.len()
+    )
+}
@@ -0,0 +1,514 @@
+//! Scoring candidates against a query.
+
+use crate::config::FusionConfig;
+use crate::store::{Entry, Transition};
+use crate::translit::Stage;
+use rayon::prelude::*;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use strsim::normalized_damerau_levenshtein;
+
+/// How much jumping from the same directory before can pull a candidate's
+/// confidence towards 1.
+pub const TRANSITION_BOOST: f64 = 0.3;
+
+/// Score of a query spelling out the initials of a directory's name, like
+/// `mwa` for `my-web-app` or `MyWebApp`.
+pub const ACRONYM_SCORE: f64 = 0.95;
+
+/// Default calibration curve, as (query length, scale) points: short
+/// queries look similar to many names, so their scores are scaled down,
+/// while long ones rarely come close and are scaled up.
+pub const DEFAULT_CALIBRATION: &[(usize, f64)] = &[(2, 0.7), (4, 0.9), (8, 1.), (16, 1.15)];
+
+/// Scale of the scores of a query `len` characters long, interpolated
+/// linearly between the points of `curve` sorted by length and flat past its
+/// ends. An empty curve disables calibration.
+pub fn calibration(len: usize, curve: &[(usize, f64)]) -> f64 {
+    let (Some(&(first_len, first)), Some(&(last_len, last))) = (curve.first(), curve.last()) else {
+        return 1.;
+    };
+    if len <= first_len {
+        return first;
+    }
+    if len >= last_len {
+        return last;
+    }
+    curve.windows(2).find(|w| len <= w[1].0).map_or(last, |w| {
+        let ((l0, s0), (l1, s1)) = (w[0], w[1]);
+        let t = (len - l0) as f64 / (l1 - l0) as f64;
+        (s1 - s0).mul_add(t, s0)
+    })
+}
+
+/// Weight of the `index`th most recent entry.
+pub fn weight(index: usize) -> f64 {
+    1.2 - (0.4 / (1. + (index as f64 / -2.).exp()))
+}
+
+/// Weight of entries in no particular order.
+pub const fn flat(_index: usize) -> f64 {
+    1.
+}
+
+/// Paths that aren't valid unicode are scored on their lossy conversion,
+/// they can still be matched by their valid parts.
+fn dist(path: &Path, query: &str, stages: &[Stage]) -> f64 {
+    let path_str = path.to_string_lossy();
+    let mut best = raw_dist(&path_str, query);
+    for stage in stages {
+        let (path, query) = (stage.apply(&path_str), stage.apply(query));
+        best = best.max(raw_dist(&path, &query));
+    }
+    best
+}
+
+fn raw_dist(path_str: &str, query: &str) -> f64 {
+    edit_score(path_str, query).max(acronym_score(path_str, query))
+}
+
+/// Similarity of the query to the full path or the directory's name.
+fn edit_score(path_str: &str, query: &str) -> f64 {
+    let basename = Path::new(path_str).file_name().and_then(|s| s.to_str());
+
+    let full_dist = normalized_damerau_levenshtein(path_str, query);
+    let base_dist = basename
+        .map(|n| normalized_damerau_levenshtein(n, query))
+        .unwrap_or(0.);
+    let base_icase_dist = basename
+        .map(|n| {
+            normalized_damerau_levenshtein(&n.to_ascii_lowercase(), &query.to_ascii_lowercase())
+        })
+        .unwrap_or(0.);
+
+    full_dist.max(base_dist).max(base_icase_dist * 0.9)
+}
+
+fn acronym_score(path_str: &str, query: &str) -> f64 {
+    Path::new(path_str)
+        .file_name()
+        .and_then(|s| s.to_str())
+        .filter(|n| is_acronym(n, query))
+        .map_or(0., |_| ACRONYM_SCORE)
+}
+
+/// The directory's name starts with the query, ignoring case, scored by how
+/// much of the name it covers.
+fn prefix_score(path_str: &str, query: &str) -> f64 {
+    let Some(name) = Path::new(path_str).file_name().and_then(|s| s.to_str()) else {
+        return 0.;
+    };
+    let (name, query) = (name.to_lowercase(), query.to_lowercase());
+    if query.is_empty() || !name.starts_with(&query) {
+        return 0.;
+    }
+    query.chars().count() as f64 / name.chars().count() as f64
+}
+
+/// The query's characters appear in order in the directory's name,
+/// ignoring case, scored by how much of the name they cover.
+fn subsequence_score(path_str: &str, query: &str) -> f64 {
+    let Some(name) = Path::new(path_str).file_name().and_then(|s| s.to_str()) else {
+        return 0.;
+    };
+    let name = name.to_lowercase();
+    let query = query.to_lowercase();
+    let mut chars = name.chars();
+    let found = query.chars().all(|q| chars.any(|c| c == q));
+    if query.is_empty() || !found {
+        return 0.;
+    }
+    query.chars().count() as f64 / name.chars().count() as f64
+}
+
+/// Matching strategies, in the order of `strategy_scores`.
+const STRATEGIES: usize = 4;
+
+/// Score of each strategy for `path`, the best of the original and each
+/// transliteration stage, lossily for paths that aren't valid unicode like
+/// `dist`.
+fn strategy_scores(path: &Path, query: &str, stages: &[Stage]) -> [f64; STRATEGIES] {
+    let path_str = path.to_string_lossy();
+    let score = |path: &str, query: &str| {
+        [
+            edit_score(path, query),
+            subsequence_score(path, query),
+            prefix_score(path, query),
+            acronym_score(path, query),
+        ]
+    };
+    let mut best = score(&path_str, query);
+    for stage in stages {
+        let other = score(&stage.apply(&path_str), &stage.apply(query));
+        for (best, other) in best.iter_mut().zip(other) {
+            *best = best.max(other);
+        }
+    }
+    best
+}
+
+/// Initials of the words in `name`, split on separators and camelCase humps.
+fn initials(name: &str) -> String {
+    let mut initials = String::new();
+    // as if preceded by a separator, so the first letter starts a word
+    let mut prev = ' ';
+    for c in name.chars() {
+        let starts_word = c.is_alphanumeric()
+            && (!prev.is_alphanumeric()
+                || (prev.is_lowercase() && c.is_uppercase())
+                || (prev.is_alphabetic() && c.is_numeric()));
+        if starts_word {
+            initials.extend(c.to_lowercase());
+        }
+        prev = c;
+    }
+    initials
+}
+
+fn is_acronym(name: &str, query: &str) -> bool {
+    let initials = initials(name);
+    initials.chars().count() >= 2 && initials == query.to_lowercase()
+}
+
+/// Indices of the characters of `path` that matched `query`, for
+/// highlighting: the last occurrence of the query ignoring case if there is
+/// one, otherwise its characters found in order, starting from the end so
+/// the directory's own name is preferred. Characters of a misspelled query
+/// that aren't in the path are left out.
+pub fn positions(path: &str, query: &str) -> Vec<usize> {
+    // one char for one char, so indices stay those of `path`
+    let lower = |s: &str| -> Vec<char> {
+        s.chars()
+            .map(|c| c.to_lowercase().next().unwrap_or(c))
+            .collect()
+    };
+    let (path, query) = (lower(path), lower(query));
+    if query.is_empty() || query.len() > path.len() {
+        return vec![];
+    }
+    if let Some(start) = (0..=path.len() - query.len())
+        .rev()
+        .find(|&i| path[i..i + query.len()] == query[..])
+    {
+        return (start..start + query.len()).collect();
+    }
+
+    let mut found = vec![];
+    let mut end = path.len();
+    for c in query.iter().rev() {
+        if let Some(i) = path[..end].iter().rposition(|p| p == c) {
+            found.push(i);
+            end = i;
+        }
+    }
+    found.reverse();
+    found
+}
+
+/// How many of the first `len` entries can score above `min_confidence`.
+/// Scores are at most the entry's weight and weights only decrease with the
+/// index, so past the first weight that can't beat the threshold no entry
+/// can. Recency weights never drop below 0.8 and flat ones stay at 1: most
+/// thresholds keep every entry, which the binary search finds in a few
+/// steps instead of weighing each of them.
+fn scored_prefix(len: usize, min_confidence: f64, weights: fn(usize) -> f64) -> usize {
+    let (mut low, mut high) = (0, len);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if weights(mid) > min_confidence {
+            low = mid + 1;
+        } else {
+            high = mid;
+        }
+    }
+    low
+}
+
+/// Score every entry, most recent first, against `input`, keeping those above
+/// `min_confidence`, best match first.
+pub fn rank<'a>(
+    entries: &'a [Entry],
+    input: &str,
+    min_confidence: f64,
+    stages: &[Stage],
+) -> eyre::Result<Vec<(f64, &'a PathBuf)>> {
+    rank_weighted(entries, input, min_confidence, weight, stages)
+}
+
+/// Same as `rank`, with `weights` giving the weight of each position.
+pub fn rank_weighted<'a>(
+    entries: &'a [Entry],
+    input: &str,
+    min_confidence: f64,
+    weights: fn(usize) -> f64,
+    stages: &[Stage],
+) -> eyre::Result<Vec<(f64, &'a PathBuf)>> {
+    let candidates = scored_prefix(entries.len(), min_confidence, weights);
+    let mut paths: Vec<(f64, &PathBuf)> = entries[..candidates]
+        .par_iter()
+        .enumerate()
+        .map(|(i, entry)| (dist(&entry.path, input, stages) * weights(i), &entry.path))
+        .filter(|(confidence, _)| *confidence > min_confidence)
+        .collect();
+
+    paths.par_sort_by(|(weight1, _), (weight2, _)| weight2.total_cmp(weight1));
+    Ok(paths)
+}
+
+/// How many entries `stream_weighted` scores together.
+const STREAM_CHUNK: usize = 512;
+
+/// Same as `rank_weighted`, but matches are handed to `emit` in the order of
+/// `entries` instead of being collected and sorted: chunks of entries are
+/// scored in parallel and emitted as soon as every chunk before them is.
+/// Scoring stops once `emit` returns `false`.
+pub fn stream_weighted(
+    entries: &[Entry],
+    input: &str,
+    min_confidence: f64,
+    weights: fn(usize) -> f64,
+    stages: &[Stage],
+    mut emit: impl FnMut(f64, &PathBuf) -> eyre::Result<bool>,
+) -> eyre::Result<()> {
+    let candidates = scored_prefix(entries.len(), min_confidence, weights);
+    let stop = AtomicBool::new(false);
+    let (tx, rx) = mpsc::channel();
+    std::thread::scope(|scope| {
+        let stop = &stop;
+        scope.spawn(move || {
+            entries[..candidates]
+                .par_chunks(STREAM_CHUNK)
+                .enumerate()
+                .for_each_with(tx, |tx, (n, chunk)| {
+                    if stop.load(Ordering::Relaxed) {
+                        return;
+                    }
+                    let scored: Vec<(f64, usize)> = chunk
+                        .iter()
+                        .enumerate()
+                        .map(|(j, entry)| {
+                            let i = n * STREAM_CHUNK + j;
+                            (dist(&entry.path, input, stages) * weights(i), i)
+                        })
+                        .filter(|(confidence, _)| *confidence > min_confidence)
+                        .collect();
+                    // the receiver is gone once emitting stopped
+                    let _ = tx.send((n, scored));
+                });
+        });
+
+        // chunks arrive in any order, hold them until their turn
+        let mut pending = BTreeMap::new();
+        let mut next = 0;
+        let res = (|| {
+            for (n, scored) in rx.iter() {
+                pending.insert(n, scored);
+                while let Some(scored) = pending.remove(&next) {
+                    next += 1;
+                    for (confidence, i) in scored {
+                        if !emit(confidence, &entries[i].path)? {
+                            return Ok(());
+                        }
+                    }
+                }
+            }
+            Ok(())
+        })();
+        stop.store(true, Ordering::Relaxed);
+        res
+    })
+}
+
+/// Same as `rank_weighted`, but each strategy (edit distance, subsequence,
+/// prefix, acronym) ranks the entries on its own and the rankings are merged
+/// by weighted reciprocal rank fusion. The best fused entry keeps the
+/// confidence of its best strategy, the others get a share of it
+/// proportional to their fused score.
+pub fn rank_fused<'a>(
+    entries: &'a [Entry],
+    input: &str,
+    min_confidence: f64,
+    weights: fn(usize) -> f64,
+    stages: &[Stage],
+    fusion: &FusionConfig,
+) -> eyre::Result<Vec<(f64, &'a PathBuf)>> {
+    let candidates = scored_prefix(entries.len(), min_confidence, weights);
+    let scored: Vec<[f64; STRATEGIES]> = entries[..candidates]
+        .par_iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            strategy_scores(&entry.path, input, stages).map(|score| score * weights(i))
+        })
+        .collect();
+
+    let strategy_weights = [
+        fusion.edit,
+        fusion.subsequence,
+        fusion.prefix,
+        fusion.acronym,
+    ];
+    let mut fused = vec![0.; scored.len()];
+    for (strategy, &strategy_weight) in strategy_weights.iter().enumerate() {
+        if strategy_weight <= 0. {
+            continue;
+        }
+        let mut ranking: Vec<usize> = (0..scored.len())
+            .filter(|&i| scored[i][strategy] > 0.)
+            .collect();
+        ranking.sort_by(|&a, &b| scored[b][strategy].total_cmp(&scored[a][strategy]));
+        for (rank, &i) in ranking.iter().enumerate() {
+            fused[i] += strategy_weight / (fusion.k + rank as f64 + 1.);
+        }
+    }
+
+    let Some(top) = (0..fused.len()).max_by(|&a, &b| fused[a].total_cmp(&fused[b])) else {
+        return Ok(vec![]);
+    };
+    if fused[top] <= 0. {
+        return Ok(vec![]);
+    }
+    let top_confidence = scored[top]
+        .iter()
+        .zip(strategy_weights)
+        .filter(|(_, weight)| *weight > 0.)
+        .map(|(score, _)| *score)
+        .fold(0., f64::max);
+    let mut paths: Vec<(f64, &PathBuf)> = fused
+        .iter()
+        .enumerate()
+        .map(|(i, score)| (score / fused[top] * top_confidence, &entries[i].path))
+        .filter(|(confidence, _)| *confidence > min_confidence)
+        .collect();
+    paths.sort_by(|(weight1, _), (weight2, _)| weight2.total_cmp(weight1));
+    Ok(paths)
+}
+
+/// Entries containing `input` (ignoring case), scored by position with a
+/// small boost for frequently visited ones.
+pub fn rank_exact<'a>(
+    entries: &'a [Entry],
+    input: &str,
+    min_confidence: f64,
+    weights: fn(usize) -> f64,
+    stages: &[Stage],
+) -> Vec<(f64, &'a PathBuf)> {
+    let input = input.to_lowercase();
+    let contains = |path: &Path| {
+        let path = path.to_string_lossy().to_lowercase();
+        path.contains(&input)
+            || stages
+                .iter()
+                .any(|stage| stage.apply(&path).contains(&stage.apply(&input)))
+    };
+    let max_visits = entries.iter().map(|e| e.visits).max().unwrap_or(0).max(1);
+    let candidates = scored_prefix(entries.len(), min_confidence, weights);
+    let mut paths: Vec<(f64, &PathBuf)> = entries[..candidates]
+        .iter()
+        .enumerate()
+        .filter(|(_, entry)| contains(&entry.path))
+        .map(|(i, entry)| {
+            let frequency = entry.visits as f64 / max_visits as f64;
+            (weights(i) * 0.1f64.mul_add(frequency, 0.9), &entry.path)
+        })
+        .filter(|(confidence, _)| *confidence > min_confidence)
+        .collect();
+
+    paths.sort_by(|(weight1, _), (weight2, _)| weight2.total_cmp(weight1));
+    paths
+}
+
+/// Raise `confidence` by up to `boost`, in proportion to the share of
+/// `path`'s visits made at the current time of the day and week, see
+/// `temporal.rs`.
+pub fn boost_temporal(
+    confidence: f64,
+    path: &Path,
+    affinity: &HashMap<PathBuf, f64>,
+    boost: f64,
+) -> f64 {
+    let share = affinity.get(path).copied().unwrap_or(0.);
+    (1. - confidence).mul_add(boost * share, confidence)
+}
+
+/// Boost a candidate by the share of jumps from the current directory that
+/// went to it.
+pub fn boost_transitions(confidence: f64, path: &Path, transitions: &[Transition]) -> f64 {
+    let total: u64 = transitions.iter().map(|t| t.count).sum();
+    if total == 0 {
+        return confidence;
+    }
+    let count = transitions
+        .iter()
+        .find(|t| t.to == path)
+        .map_or(0, |t| t.count);
+    let boost = TRANSITION_BOOST * count as f64 / total as f64;
+    (1. - confidence).mul_add(boost, confidence)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &Path) -> Entry {
+        Entry {
+            path: path.to_path_buf(),
+            last_access: 0,
+            visits: 1,
+            hosts: vec![],
+            id: None,
+        }
+    }
+
+    #[test]
+    fn entries_that_cant_beat_the_threshold_are_skipped() {
+        // weight(3) is the first under 0.9
+        assert_eq!(scored_prefix(50_000, 0.9, weight), 3);
+        assert_eq!(scored_prefix(50_000, 0.5, weight), 50_000);
+        assert_eq!(scored_prefix(50_000, 0.99, flat), 50_000);
+        assert_eq!(scored_prefix(50_000, 1., flat), 0);
+
+        // a perfect match past the cutoff isn't even scored
+        let entries: Vec<Entry> = ["/a", "/b", "/c", "/d", "/work"]
+            .iter()
+            .map(|path| entry(Path::new(path)))
+            .collect();
+        assert!(rank(&entries, "/work", 0.9, &[]).unwrap().is_empty());
+        assert_eq!(rank(&entries, "/work", 0.5, &[]).unwrap().len(), 1);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn paths_that_arent_unicode_dont_stop_the_ranking() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let invalid = Path::new(OsStr::from_bytes(b"/data/\xff/projects"));
+        let entries = [entry(invalid), entry(Path::new("/home/user/projects"))];
+
+        let ranked = rank(&entries, "projects", 0.1, &[]).unwrap();
+        let paths: Vec<_> = ranked.iter().map(|(_, path)| path.as_path()).collect();
+        assert!(paths.contains(&Path::new("/home/user/projects")));
+        assert!(paths.contains(&invalid));
+
+        let fused = rank_fused(
+            &entries,
+            "projects",
+            0.1,
+            flat,
+            &[],
+            &FusionConfig::default(),
+        )
+        .unwrap();
+        assert_eq!(fused.len(), 2);
+
+        let mut streamed = vec![];
+        stream_weighted(&entries, "projects", 0.1, flat, &[], |_, path| {
+            streamed.push(path.clone());
+            Ok(true)
+        })
+        .unwrap();
+        assert_eq!(streamed.len(), 2);
+    }
+}
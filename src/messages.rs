@@ -0,0 +1,745 @@
+//! Everything wd tells the user outside of its regular output, by language.
+//! The language comes from `LC_ALL`, `LC_MESSAGES` or `LANG`, English being
+//! the fallback. Templates name their arguments in braces; a translation
+//! only has to fill the same `Catalog`.
+
+use std::path::Path;
+use std::sync::OnceLock;
+
+pub struct Catalog {
+    pub no_match: &'static str,
+    pub ambiguous: &'static str,
+    pub read_only: &'static str,
+    pub db_corrupt: &'static str,
+    pub db_locked: &'static str,
+    pub db_insecure: &'static str,
+    pub io: &'static str,
+    pub couldnt_record_jump: &'static str,
+    pub ignoring_unreadable: &'static str,
+    pub couldnt_write: &'static str,
+    pub couldnt_watch: &'static str,
+    pub couldnt_remove: &'static str,
+    pub forgot: &'static str,
+    pub snoozed: &'static str,
+    pub copied: &'static str,
+    pub copied_count: &'static str,
+    pub suppressed: &'static str,
+    pub old_home: &'static str,
+    pub no_config_path: &'static str,
+    pub bad_host_rename: &'static str,
+    pub not_a_directory: &'static str,
+    pub no_group: &'static str,
+    pub private_dir: &'static str,
+    pub not_in_group: &'static str,
+    pub chdir_file_list: &'static str,
+    pub verify_problems: &'static str,
+    pub unknown_unit: &'static str,
+    pub missing_number: &'static str,
+    pub missing_unit: &'static str,
+    pub duration_not_positive: &'static str,
+    pub loading_db: &'static str,
+    pub writing_db: &'static str,
+    pub loading_remote_db: &'static str,
+    pub error_loading: &'static str,
+    pub error_writing: &'static str,
+    pub error_opening: &'static str,
+    pub couldnt_create: &'static str,
+    pub couldnt_read: &'static str,
+    pub couldnt_find: &'static str,
+    pub couldnt_replace: &'static str,
+    pub sqlite_schema: &'static str,
+    pub no_sqlite: &'static str,
+    pub no_entry: &'static str,
+    pub invalid_pattern: &'static str,
+    pub couldnt_find_dir: &'static str,
+    pub couldnt_start_watching: &'static str,
+    pub couldnt_run: &'static str,
+    pub command_failed: &'static str,
+    pub failed: &'static str,
+    pub env_unset: &'static str,
+    pub unknown_program: &'static str,
+    pub empty_command: &'static str,
+    pub couldnt_render_man: &'static str,
+    pub tmux_hooks: &'static str,
+    pub bad_protocol: &'static str,
+    pub no_exe: &'static str,
+    pub not_normalized: &'static str,
+    pub config_parse: &'static str,
+    pub config_read: &'static str,
+    pub config_write: &'static str,
+    pub config_not_table: &'static str,
+    pub config_not_list: &'static str,
+    pub bad_profile: &'static str,
+    pub bad_calibration: &'static str,
+    pub bad_boost: &'static str,
+    pub bad_utc_offset: &'static str,
+    pub invalid_trace: &'static str,
+    pub no_asset: &'static str,
+    pub download_failed: &'static str,
+    pub no_checksum_tool: &'static str,
+    pub bad_release_list: &'static str,
+    pub checksum_mismatch: &'static str,
+    pub couldnt_open_terminal: &'static str,
+    pub no_clipboard: &'static str,
+    pub error: &'static str,
+    pub not_unicode: &'static str,
+    pub empty_checksum: &'static str,
+}
+
+const EN: Catalog = Catalog {
+    no_match: "no match found for {query}",
+    ambiguous: "{count} matches for {query}, pick one",
+    read_only: "wd is in read-only mode (--read-only or WD_READONLY)",
+    db_corrupt: "the database {path} is corrupt: {reason}",
+    db_locked: "the database {path} is locked by another wd",
+    db_insecure: "the database {path} is writable by other users, who could make wd run \
+                  commands in your shell; fix it with `chmod go-w {path}` or set \
+                  `allow_shared_db = true` in the config",
+    io: "couldn't access {path}",
+    couldnt_record_jump: "wd: couldn't record this jump: {reason}",
+    ignoring_unreadable: "wd: ignoring unreadable {what}: {reason}",
+    couldnt_write: "wd: couldn't write {what}: {reason}",
+    couldnt_watch: "wd: couldn't watch {path}: {reason}",
+    couldnt_remove: "wd: couldn't remove {path}: {reason}",
+    forgot: "forgot {count} entries",
+    snoozed: "snoozed {count} entries",
+    copied: "copied {paths}",
+    copied_count: "copied {count} paths",
+    suppressed: "wd: you keep leaving {path} right after jumping there for {query}, \
+                 it won't be suggested for that anymore (`wdbin forget` it to drop it entirely)",
+    old_home: "wd: most entries are under {old}, which doesn't exist anymore. \
+               If it moved, run `wdbin migrate-home {old} {new}`",
+    no_config_path: "couldn't find where to write the config",
+    bad_host_rename: "expected --host old=new, got {host}",
+    not_a_directory: "{path} isn't a directory",
+    no_group: "no group named {name}",
+    private_dir: "{path} is private, wd doesn't record it",
+    not_in_group: "{path} isn't in any group",
+    chdir_file_list: "--chdir-file takes a single directory, not a --list",
+    verify_problems: "found {count} problems, `wdbin db compact` fixes them",
+    unknown_unit: "unknown unit `{unit}`, expected one of w, d, h, m, s",
+    missing_number: "missing number before `{unit}`",
+    missing_unit: "missing unit after `{number}`",
+    duration_not_positive: "duration must be positive",
+    loading_db: "error loading wd db",
+    writing_db: "error writing wd db",
+    loading_remote_db: "error loading remote db",
+    error_loading: "error loading {path}",
+    error_writing: "error writing {path}",
+    error_opening: "error opening {path}",
+    couldnt_create: "couldn't create {path}",
+    couldnt_read: "couldn't read {path}",
+    couldnt_find: "couldn't find {path}",
+    couldnt_replace: "couldn't replace {path}",
+    sqlite_schema: "error creating sqlite schema",
+    no_sqlite: "wd was built without sqlite support (enable the `sqlite` feature)",
+    no_entry: "no entry for {path}",
+    invalid_pattern: "invalid pattern {pattern}",
+    couldnt_find_dir: "couldn't find the directory",
+    couldnt_start_watching: "couldn't start watching",
+    couldnt_run: "couldn't run {program}",
+    command_failed: "{command} failed ({status})",
+    failed: "{command} failed",
+    env_unset: "${var} isn't set",
+    unknown_program: "unknown program {program}, add it to [open.programs]",
+    empty_command: "empty command for {program}",
+    couldnt_render_man: "couldn't render the man page",
+    tmux_hooks: "tmux isn't a shell hooks can run in",
+    bad_protocol: "unsupported protocol version {version}, this wd speaks {supported}",
+    no_exe: "couldn't find the running executable",
+    not_normalized: "refusing to jump to {path}, not a normalized absolute path",
+    config_parse: "error parsing config {config}",
+    config_read: "error reading config {config}",
+    config_write: "error writing config {config}",
+    config_not_table: "`{key}` isn't a table in {config}",
+    config_not_list: "`{key}` isn't a list in {config}",
+    bad_profile: "invalid profile name `{profile}`, use letters, digits, - and _",
+    bad_calibration: "`calibration` needs increasing lengths and positive scales in {config}",
+    bad_boost: "`temporal.boost` must be between 0 and 1 in {config}",
+    bad_utc_offset: "invalid `temporal.utc_offset` `{offset}` in {config}, expected like +02:00",
+    invalid_trace: "invalid trace {path}",
+    no_asset: "release {tag} has no {name}",
+    download_failed: "couldn't download {url}: {reason}",
+    no_checksum_tool: "couldn't checksum the download, install sha256sum or shasum",
+    bad_release_list: "couldn't read the release list",
+    checksum_mismatch: "checksum mismatch for {name}: expected {expected}, got {actual}",
+    couldnt_open_terminal: "couldn't open the terminal",
+    no_clipboard: "no clipboard tool worked and the terminal isn't reachable",
+    error: "Error: {reason}",
+    not_unicode: "{path} isn't valid unicode",
+    empty_checksum: "empty checksum for {name}",
+};
+
+const FR: Catalog = Catalog {
+    no_match: "aucun résultat pour {query}",
+    ambiguous: "{count} résultats pour {query}, choisissez-en un",
+    read_only: "wd est en lecture seule (--read-only ou WD_READONLY)",
+    db_corrupt: "la base {path} est corrompue : {reason}",
+    db_locked: "la base {path} est verrouillée par un autre wd",
+    db_insecure: "la base {path} est modifiable par d'autres utilisateurs, qui pourraient \
+                  faire exécuter des commandes à votre shell ; corrigez-le avec \
+                  `chmod go-w {path}` ou mettez `allow_shared_db = true` dans la configuration",
+    io: "impossible d'accéder à {path}",
+    couldnt_record_jump: "wd : impossible d'enregistrer ce saut : {reason}",
+    ignoring_unreadable: "wd : {what} illisible ignoré : {reason}",
+    couldnt_write: "wd : impossible d'écrire {what} : {reason}",
+    couldnt_watch: "wd : impossible de surveiller {path} : {reason}",
+    couldnt_remove: "wd : impossible de supprimer {path} : {reason}",
+    forgot: "{count} entrées oubliées",
+    snoozed: "{count} entrées mises en pause",
+    copied: "copié : {paths}",
+    copied_count: "{count} chemins copiés",
+    suppressed: "wd : vous quittez souvent {path} juste après y être allé pour {query}, \
+                 il ne sera plus proposé pour cette recherche (`wdbin forget` pour l'oublier)",
+    old_home: "wd : la plupart des entrées sont sous {old}, qui n'existe plus. \
+               S'il a été déplacé, lancez `wdbin migrate-home {old} {new}`",
+    no_config_path: "impossible de trouver où écrire la configuration",
+    bad_host_rename: "--host ancien=nouveau attendu, reçu {host}",
+    not_a_directory: "{path} n'est pas un répertoire",
+    no_group: "aucun groupe nommé {name}",
+    private_dir: "{path} est privé, wd ne l'enregistre pas",
+    not_in_group: "{path} n'est dans aucun groupe",
+    chdir_file_list: "--chdir-file prend un seul répertoire, pas une --list",
+    verify_problems: "{count} problèmes trouvés, `wdbin db compact` les corrige",
+    unknown_unit: "unité `{unit}` inconnue, w, d, h, m ou s attendue",
+    missing_number: "nombre manquant avant `{unit}`",
+    missing_unit: "unité manquante après `{number}`",
+    duration_not_positive: "la durée doit être positive",
+    loading_db: "erreur au chargement de la base wd",
+    writing_db: "erreur à l'écriture de la base wd",
+    loading_remote_db: "erreur au chargement de la base distante",
+    error_loading: "erreur au chargement de {path}",
+    error_writing: "erreur à l'écriture de {path}",
+    error_opening: "erreur à l'ouverture de {path}",
+    couldnt_create: "impossible de créer {path}",
+    couldnt_read: "impossible de lire {path}",
+    couldnt_find: "impossible de trouver {path}",
+    couldnt_replace: "impossible de remplacer {path}",
+    sqlite_schema: "erreur à la création du schéma sqlite",
+    no_sqlite: "wd a été compilé sans sqlite (activez la feature `sqlite`)",
+    no_entry: "aucune entrée pour {path}",
+    invalid_pattern: "motif invalide {pattern}",
+    couldnt_find_dir: "impossible de trouver le répertoire",
+    couldnt_start_watching: "impossible de lancer la surveillance",
+    couldnt_run: "impossible de lancer {program}",
+    command_failed: "{command} a échoué ({status})",
+    failed: "{command} a échoué",
+    env_unset: "${var} n'est pas définie",
+    unknown_program: "programme {program} inconnu, ajoutez-le à [open.programs]",
+    empty_command: "commande vide pour {program}",
+    couldnt_render_man: "impossible de générer la page de manuel",
+    tmux_hooks: "tmux n'est pas un shell où les hooks peuvent tourner",
+    bad_protocol: "version de protocole {version} non prise en charge, ce wd parle la {supported}",
+    no_exe: "impossible de trouver l'exécutable en cours",
+    not_normalized: "saut vers {path} refusé, ce n'est pas un chemin absolu normalisé",
+    config_parse: "erreur d'analyse de la configuration {config}",
+    config_read: "erreur de lecture de la configuration {config}",
+    config_write: "erreur à l'écriture de la configuration {config}",
+    config_not_table: "`{key}` n'est pas une table dans {config}",
+    config_not_list: "`{key}` n'est pas une liste dans {config}",
+    bad_profile: "nom de profil `{profile}` invalide, utilisez des lettres, des chiffres, - et _",
+    bad_calibration:
+        "`calibration` demande des longueurs croissantes et des échelles positives dans {config}",
+    bad_boost: "`temporal.boost` doit être entre 0 et 1 dans {config}",
+    bad_utc_offset: "`temporal.utc_offset` `{offset}` invalide dans {config}, attendu comme +02:00",
+    invalid_trace: "trace invalide {path}",
+    no_asset: "la version {tag} n'a pas de {name}",
+    download_failed: "impossible de télécharger {url} : {reason}",
+    no_checksum_tool: "impossible de calculer la somme de contrôle, installez sha256sum ou shasum",
+    bad_release_list: "impossible de lire la liste des versions",
+    checksum_mismatch:
+        "somme de contrôle incorrecte pour {name} : {expected} attendue, {actual} obtenue",
+    couldnt_open_terminal: "impossible d'ouvrir le terminal",
+    no_clipboard: "aucun presse-papiers n'a marché et le terminal est injoignable",
+    error: "Erreur : {reason}",
+    not_unicode: "{path} n'est pas de l'unicode valide",
+    empty_checksum: "somme de contrôle vide pour {name}",
+};
+
+fn language() -> Option<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty())
+}
+
+pub fn catalog() -> &'static Catalog {
+    static CATALOG: OnceLock<&'static Catalog> = OnceLock::new();
+    CATALOG.get_or_init(|| match language() {
+        Some(lang) if lang.starts_with("fr") => &FR,
+        _ => &EN,
+    })
+}
+
+/// `template` with each `{name}` replaced by its value, in a single pass so
+/// braces in the values are left alone.
+fn fill(template: &str, args: &[(&str, &str)]) -> String {
+    let mut text = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        text.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let arg = after.find('}').and_then(|end| {
+            args.iter()
+                .find(|(name, _)| *name == &after[..end])
+                .map(|(_, value)| (end, value))
+        });
+        match arg {
+            Some((end, value)) => {
+                text.push_str(value);
+                rest = &after[end + 1..];
+            }
+            None => {
+                text.push('{');
+                rest = after;
+            }
+        }
+    }
+    text.push_str(rest);
+    text
+}
+
+pub fn no_match(query: &str) -> String {
+    fill(catalog().no_match, &[("query", query)])
+}
+
+pub fn ambiguous(query: &str, count: usize) -> String {
+    fill(
+        catalog().ambiguous,
+        &[("query", query), ("count", &count.to_string())],
+    )
+}
+
+pub fn read_only() -> String {
+    catalog().read_only.to_string()
+}
+
+pub fn db_corrupt(path: &Path, reason: &str) -> String {
+    fill(
+        catalog().db_corrupt,
+        &[("path", &path.display().to_string()), ("reason", reason)],
+    )
+}
+
+pub fn db_locked(path: &Path) -> String {
+    fill(
+        catalog().db_locked,
+        &[("path", &path.display().to_string())],
+    )
+}
+
+pub fn db_insecure(path: &Path) -> String {
+    fill(
+        catalog().db_insecure,
+        &[("path", &path.display().to_string())],
+    )
+}
+
+pub fn io(path: &Path) -> String {
+    fill(catalog().io, &[("path", &path.display().to_string())])
+}
+
+pub fn couldnt_record_jump(err: &eyre::Report) -> String {
+    fill(
+        catalog().couldnt_record_jump,
+        &[("reason", &format!("{err:#}"))],
+    )
+}
+
+pub fn ignoring_unreadable(what: &str, err: &eyre::Report) -> String {
+    fill(
+        catalog().ignoring_unreadable,
+        &[("what", what), ("reason", &format!("{err:#}"))],
+    )
+}
+
+pub fn couldnt_write(what: &str, err: &eyre::Report) -> String {
+    fill(
+        catalog().couldnt_write,
+        &[("what", what), ("reason", &format!("{err:#}"))],
+    )
+}
+
+pub fn couldnt_watch(path: &Path, err: &notify::Error) -> String {
+    fill(
+        catalog().couldnt_watch,
+        &[
+            ("path", &path.display().to_string()),
+            ("reason", &err.to_string()),
+        ],
+    )
+}
+
+pub fn couldnt_remove(path: &Path, err: &std::io::Error) -> String {
+    fill(
+        catalog().couldnt_remove,
+        &[
+            ("path", &path.display().to_string()),
+            ("reason", &err.to_string()),
+        ],
+    )
+}
+
+pub fn forgot(count: usize) -> String {
+    fill(catalog().forgot, &[("count", &count.to_string())])
+}
+
+pub fn snoozed(count: usize) -> String {
+    fill(catalog().snoozed, &[("count", &count.to_string())])
+}
+
+pub fn copied(paths: &[String]) -> String {
+    fill(catalog().copied, &[("paths", &paths.join(", "))])
+}
+
+pub fn copied_count(count: usize) -> String {
+    fill(catalog().copied_count, &[("count", &count.to_string())])
+}
+
+pub fn suppressed(path: &Path, query: &str) -> String {
+    fill(
+        catalog().suppressed,
+        &[
+            ("path", &path.display().to_string()),
+            ("query", &format!("{query:?}")),
+        ],
+    )
+}
+
+pub fn old_home(old: &Path, new: &Path) -> String {
+    fill(
+        catalog().old_home,
+        &[
+            ("old", &old.display().to_string()),
+            ("new", &new.display().to_string()),
+        ],
+    )
+}
+
+pub fn no_config_path() -> String {
+    catalog().no_config_path.to_string()
+}
+
+pub fn bad_host_rename(host: &str) -> String {
+    fill(catalog().bad_host_rename, &[("host", host)])
+}
+
+pub fn not_a_directory(path: &Path) -> String {
+    fill(
+        catalog().not_a_directory,
+        &[("path", &path.display().to_string())],
+    )
+}
+
+pub fn no_group(name: &str) -> String {
+    fill(catalog().no_group, &[("name", name)])
+}
+
+pub fn private_dir(path: &Path) -> String {
+    fill(
+        catalog().private_dir,
+        &[("path", &path.display().to_string())],
+    )
+}
+
+pub fn not_in_group(path: &Path) -> String {
+    fill(
+        catalog().not_in_group,
+        &[("path", &path.display().to_string())],
+    )
+}
+
+pub fn chdir_file_list() -> String {
+    catalog().chdir_file_list.to_string()
+}
+
+pub fn verify_problems(count: usize) -> String {
+    fill(catalog().verify_problems, &[("count", &count.to_string())])
+}
+
+pub fn unknown_unit(unit: char) -> String {
+    fill(catalog().unknown_unit, &[("unit", &unit.to_string())])
+}
+
+pub fn missing_number(unit: char) -> String {
+    fill(catalog().missing_number, &[("unit", &unit.to_string())])
+}
+
+pub fn missing_unit(number: &str) -> String {
+    fill(catalog().missing_unit, &[("number", number)])
+}
+
+pub fn duration_not_positive() -> String {
+    catalog().duration_not_positive.to_string()
+}
+
+pub fn loading_db() -> String {
+    catalog().loading_db.to_string()
+}
+
+pub fn writing_db() -> String {
+    catalog().writing_db.to_string()
+}
+
+pub fn loading_remote_db() -> String {
+    catalog().loading_remote_db.to_string()
+}
+
+pub fn error_loading(path: &Path) -> String {
+    fill(
+        catalog().error_loading,
+        &[("path", &path.display().to_string())],
+    )
+}
+
+pub fn error_writing(path: &Path) -> String {
+    fill(
+        catalog().error_writing,
+        &[("path", &path.display().to_string())],
+    )
+}
+
+#[cfg_attr(not(feature = "sqlite"), allow(dead_code))]
+pub fn error_opening(path: &Path) -> String {
+    fill(
+        catalog().error_opening,
+        &[("path", &path.display().to_string())],
+    )
+}
+
+pub fn couldnt_create(path: &Path) -> String {
+    fill(
+        catalog().couldnt_create,
+        &[("path", &path.display().to_string())],
+    )
+}
+
+#[cfg_attr(not(feature = "simulate"), allow(dead_code))]
+pub fn couldnt_read(path: &Path) -> String {
+    fill(
+        catalog().couldnt_read,
+        &[("path", &path.display().to_string())],
+    )
+}
+
+pub fn couldnt_find(path: &Path) -> String {
+    fill(
+        catalog().couldnt_find,
+        &[("path", &path.display().to_string())],
+    )
+}
+
+pub fn couldnt_replace(path: &Path) -> String {
+    fill(
+        catalog().couldnt_replace,
+        &[("path", &path.display().to_string())],
+    )
+}
+
+#[cfg_attr(not(feature = "sqlite"), allow(dead_code))]
+pub fn sqlite_schema() -> String {
+    catalog().sqlite_schema.to_string()
+}
+
+#[cfg_attr(feature = "sqlite", allow(dead_code))]
+pub fn no_sqlite() -> String {
+    catalog().no_sqlite.to_string()
+}
+
+pub fn no_entry(path: &Path) -> String {
+    fill(catalog().no_entry, &[("path", &path.display().to_string())])
+}
+
+pub fn invalid_pattern(pattern: &str) -> String {
+    fill(catalog().invalid_pattern, &[("pattern", pattern)])
+}
+
+pub fn couldnt_find_dir() -> String {
+    catalog().couldnt_find_dir.to_string()
+}
+
+pub fn couldnt_start_watching() -> String {
+    catalog().couldnt_start_watching.to_string()
+}
+
+pub fn couldnt_run(program: &str) -> String {
+    fill(catalog().couldnt_run, &[("program", program)])
+}
+
+pub fn command_failed(command: &str, status: std::process::ExitStatus) -> String {
+    fill(
+        catalog().command_failed,
+        &[("command", command), ("status", &status.to_string())],
+    )
+}
+
+pub fn failed(command: &str) -> String {
+    fill(catalog().failed, &[("command", command)])
+}
+
+pub fn env_unset(var: &str) -> String {
+    fill(catalog().env_unset, &[("var", var)])
+}
+
+pub fn unknown_program(program: &str) -> String {
+    fill(
+        catalog().unknown_program,
+        &[("program", &format!("{program:?}"))],
+    )
+}
+
+pub fn empty_command(program: &str) -> String {
+    fill(
+        catalog().empty_command,
+        &[("program", &format!("{program:?}"))],
+    )
+}
+
+pub fn couldnt_render_man() -> String {
+    catalog().couldnt_render_man.to_string()
+}
+
+pub fn tmux_hooks() -> String {
+    catalog().tmux_hooks.to_string()
+}
+
+pub fn bad_protocol(version: &str, supported: &str) -> String {
+    fill(
+        catalog().bad_protocol,
+        &[("version", version), ("supported", supported)],
+    )
+}
+
+pub fn no_exe() -> String {
+    catalog().no_exe.to_string()
+}
+
+pub fn not_normalized(path: &Path) -> String {
+    fill(
+        catalog().not_normalized,
+        &[("path", &path.display().to_string())],
+    )
+}
+
+pub fn config_parse(config: &str) -> String {
+    fill(catalog().config_parse, &[("config", config)])
+}
+
+pub fn config_read(config: &Path) -> String {
+    fill(
+        catalog().config_read,
+        &[("config", &config.display().to_string())],
+    )
+}
+
+pub fn config_write(config: &Path) -> String {
+    fill(
+        catalog().config_write,
+        &[("config", &config.display().to_string())],
+    )
+}
+
+pub fn config_not_table(key: &str, config: &Path) -> String {
+    fill(
+        catalog().config_not_table,
+        &[("key", key), ("config", &config.display().to_string())],
+    )
+}
+
+pub fn config_not_list(key: &str, config: &Path) -> String {
+    fill(
+        catalog().config_not_list,
+        &[("key", key), ("config", &config.display().to_string())],
+    )
+}
+
+pub fn bad_profile(profile: &str) -> String {
+    fill(catalog().bad_profile, &[("profile", profile)])
+}
+
+pub fn bad_calibration(config: &str) -> String {
+    fill(catalog().bad_calibration, &[("config", config)])
+}
+
+pub fn bad_boost(config: &str) -> String {
+    fill(catalog().bad_boost, &[("config", config)])
+}
+
+pub fn bad_utc_offset(offset: &str, config: &str) -> String {
+    fill(
+        catalog().bad_utc_offset,
+        &[("offset", offset), ("config", config)],
+    )
+}
+
+#[cfg_attr(not(feature = "simulate"), allow(dead_code))]
+pub fn invalid_trace(path: &Path) -> String {
+    fill(
+        catalog().invalid_trace,
+        &[("path", &path.display().to_string())],
+    )
+}
+
+pub fn no_asset(tag: &str, name: &str) -> String {
+    fill(catalog().no_asset, &[("tag", tag), ("name", name)])
+}
+
+pub fn download_failed(url: &str, reason: &str) -> String {
+    fill(
+        catalog().download_failed,
+        &[("url", url), ("reason", reason)],
+    )
+}
+
+pub fn no_checksum_tool() -> String {
+    catalog().no_checksum_tool.to_string()
+}
+
+pub fn bad_release_list() -> String {
+    catalog().bad_release_list.to_string()
+}
+
+pub fn checksum_mismatch(name: &str, expected: &str, actual: &str) -> String {
+    fill(
+        catalog().checksum_mismatch,
+        &[("name", name), ("expected", expected), ("actual", actual)],
+    )
+}
+
+pub fn couldnt_open_terminal() -> String {
+    catalog().couldnt_open_terminal.to_string()
+}
+
+pub fn no_clipboard() -> String {
+    catalog().no_clipboard.to_string()
+}
+
+pub fn error(reason: &eyre::Report) -> String {
+    fill(catalog().error, &[("reason", &format!("{reason:?}"))])
+}
+
+pub fn not_unicode(path: &Path) -> String {
+    fill(
+        catalog().not_unicode,
+        &[("path", &path.display().to_string())],
+    )
+}
+
+pub fn empty_checksum(name: &str) -> String {
+    fill(catalog().empty_checksum, &[("name", name)])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_are_not_filled_in_again() {
+        let text = fill(
+            "{old} moved to {new}",
+            &[("old", "/tmp/{new}"), ("new", "/srv/{old}")],
+        );
+        assert_eq!(text, "/tmp/{new} moved to /srv/{old}");
+        assert_eq!(fill("{unknown} {", &[]), "{unknown} {");
+    }
+}
@@ -0,0 +1,91 @@
+//! Rewriting stored paths after the home directory moved, like after an OS
+//! reinstall with a different user name, and renaming hosts.
+
+use crate::maintenance;
+use crate::store::{DBContent, Entry};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// `path` moved from under `old` to under `new`, if it was under `old`.
+fn moved(path: &Path, old: &Path, new: &Path) -> Option<PathBuf> {
+    let rest = path.strip_prefix(old).ok()?;
+    if rest.as_os_str().is_empty() {
+        return Some(new.to_path_buf());
+    }
+    Some(new.join(rest))
+}
+
+fn rewrite(path: &mut PathBuf, old: &Path, new: &Path) -> bool {
+    let Some(moved) = moved(path, old, new) else {
+        return false;
+    };
+    *path = moved;
+    true
+}
+
+/// Move every path under `old` to `new`, merging entries that end up on the
+/// same path. Returns how many entries moved.
+pub fn move_home(content: &mut DBContent, old: &Path, new: &Path) -> usize {
+    let mut moved = 0;
+    for entry in &mut content.paths {
+        moved += usize::from(rewrite(&mut entry.path, old, new));
+    }
+    maintenance::merge_entries(content);
+
+    for t in &mut content.forgotten {
+        rewrite(&mut t.path, old, new);
+    }
+    for s in &mut content.suppressed {
+        rewrite(&mut s.path, old, new);
+    }
+    content.suppressed.sort();
+    content.suppressed.dedup();
+    for s in &mut content.snoozed {
+        rewrite(&mut s.path, old, new);
+    }
+    for j in &mut content.jumps {
+        rewrite(&mut j.path, old, new);
+    }
+    for t in &mut content.transitions {
+        rewrite(&mut t.from, old, new);
+        rewrite(&mut t.to, old, new);
+    }
+    maintenance::merge_transitions(content);
+    for path in content.groups.iter_mut().flat_map(|g| &mut g.paths) {
+        rewrite(path, old, new);
+    }
+    moved
+}
+
+/// Rename the host tag `old` to `new`. Returns how many entries were tagged.
+pub fn rename_host(content: &mut DBContent, old: &str, new: &str) -> usize {
+    let mut renamed = 0;
+    for entry in &mut content.paths {
+        if let Some(i) = entry.hosts.iter().position(|h| h == old) {
+            entry.hosts.remove(i);
+            if !entry.hosts.iter().any(|h| h == new) {
+                entry.hosts.push(new.to_string());
+            }
+            renamed += 1;
+        }
+    }
+    renamed
+}
+
+/// The previous home directory, if most entries live under the same
+/// `/home/<user>`-like directory that isn't `home` and doesn't exist anymore.
+pub fn detect_old_home(entries: &[Entry], home: &Path) -> Option<PathBuf> {
+    let mut counts: HashMap<PathBuf, usize> = HashMap::new();
+    for entry in entries {
+        if entry.path.starts_with(home) {
+            continue;
+        }
+        // `/home/user` or `/Users/user`: the first two components past the root
+        let prefix: PathBuf = entry.path.components().take(3).collect();
+        if prefix.components().count() == 3 {
+            *counts.entry(prefix).or_insert(0) += 1;
+        }
+    }
+    let (prefix, count) = counts.into_iter().max_by_key(|&(_, count)| count)?;
+    (count * 2 > entries.len() && !prefix.exists()).then_some(prefix)
+}
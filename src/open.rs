@@ -0,0 +1,83 @@
+//! Launching programs in a matched directory.
+
+use crate::messages;
+use eyre::{bail, eyre, Context};
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Command;
+
+#[cfg(target_os = "macos")]
+const FILE_MANAGER: &str = "open {path}";
+#[cfg(not(target_os = "macos"))]
+const FILE_MANAGER: &str = "xdg-open {path}";
+
+const PATH_PLACEHOLDER: &str = "{path}";
+
+/// Programs available without any configuration.
+fn builtin(name: &str) -> Option<&'static str> {
+    match name {
+        "editor" => Some("$EDITOR {path}"),
+        "files" => Some(FILE_MANAGER),
+        "code" => Some("code {path}"),
+        _ => None,
+    }
+}
+
+/// Turn a template into a command line: words are split on whitespace,
+/// `{path}` is replaced by `path` and words starting with `$` by the
+/// environment variable they name, itself split on whitespace so
+/// `EDITOR="code --wait"` works. Without any `{path}`, the path is appended.
+fn command_line(template: &str, path: &Path) -> eyre::Result<Vec<String>> {
+    let path = path
+        .to_str()
+        .ok_or_else(|| eyre!(messages::not_unicode(path)))?;
+    let mut args = vec![];
+    for word in template.split_whitespace() {
+        match word.strip_prefix('$') {
+            Some(var) => {
+                let value = std::env::var(var).wrap_err_with(|| messages::env_unset(var))?;
+                args.extend(value.split_whitespace().map(str::to_string));
+            }
+            None => args.push(word.replace(PATH_PLACEHOLDER, path)),
+        }
+    }
+    if !template.contains(PATH_PLACEHOLDER) {
+        args.push(path.to_string());
+    }
+    Ok(args)
+}
+
+/// Run `program`, either configured in `programs` or a builtin one, in `path`.
+pub fn launch(program: &str, programs: &HashMap<String, String>, path: &Path) -> eyre::Result<()> {
+    let template = programs
+        .get(program)
+        .map(String::as_str)
+        .or_else(|| builtin(program))
+        .ok_or_else(|| eyre!(messages::unknown_program(program)))?;
+    let args = command_line(template, path)?;
+    let (cmd, args) = args
+        .split_first()
+        .ok_or_else(|| eyre!(messages::empty_command(program)))?;
+
+    let status = Command::new(cmd)
+        .args(args)
+        .current_dir(path)
+        .status()
+        .wrap_err_with(|| messages::couldnt_run(cmd))?;
+    if !status.success() {
+        bail!(messages::command_failed(cmd, status));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn editor_with_arguments() {
+        std::env::set_var("WD_TEST_EDITOR", "code --wait");
+        let args = command_line("$WD_TEST_EDITOR {path}", Path::new("/home/user/my project"));
+        assert_eq!(args.unwrap(), ["code", "--wait", "/home/user/my project"]);
+    }
+}
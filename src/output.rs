@@ -0,0 +1,82 @@
+//! Printing paths so that any valid directory name survives: with
+//! `--print0` every record ends with a NUL instead of a newline and paths are
+//! written byte for byte, and paths shown on a terminal have their control
+//! characters escaped so a newline in a name can't pass for two entries.
+
+use std::borrow::Cow;
+use std::io::{IsTerminal, Write};
+use std::path::Path;
+
+/// `path` for a person to read, with newlines, tabs and other control
+/// characters escaped like `\n`.
+pub fn escape(path: &Path) -> Cow<'_, str> {
+    let shown = path.to_string_lossy();
+    if !shown.chars().any(char::is_control) {
+        return shown;
+    }
+    shown
+        .chars()
+        .map(|c| {
+            if c.is_control() {
+                c.escape_default().to_string()
+            } else {
+                c.to_string()
+            }
+        })
+        .collect::<String>()
+        .into()
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Output {
+    pub print0: bool,
+    /// escape paths, for stdout being a terminal
+    pub human: bool,
+}
+
+impl Output {
+    pub fn stdout(print0: bool) -> Self {
+        Self {
+            print0,
+            human: !print0 && std::io::stdout().is_terminal(),
+        }
+    }
+
+    /// `path` as part of a record: escaped for a person, as is otherwise.
+    pub fn show(self, path: &Path) -> Cow<'_, str> {
+        if self.human {
+            escape(path)
+        } else {
+            path.to_string_lossy()
+        }
+    }
+
+    /// Print `record` and its terminator.
+    pub fn record(self, record: &str) -> std::io::Result<()> {
+        let mut out = std::io::stdout().lock();
+        out.write_all(record.as_bytes())?;
+        out.write_all(if self.print0 { b"\0" } else { b"\n" })
+    }
+
+    /// Print `path` alone as a record, byte for byte unless it's for a
+    /// person.
+    pub fn path(self, path: &Path) -> std::io::Result<()> {
+        if self.human {
+            return self.record(&escape(path));
+        }
+        let mut out = std::io::stdout().lock();
+        write_path(&mut out, path)?;
+        out.write_all(if self.print0 { b"\0" } else { b"\n" })
+    }
+}
+
+#[cfg(unix)]
+fn write_path(out: &mut impl Write, path: &Path) -> std::io::Result<()> {
+    use std::os::unix::ffi::OsStrExt;
+    out.write_all(path.as_os_str().as_bytes())
+}
+
+#[cfg(not(unix))]
+fn write_path(out: &mut impl Write, path: &Path) -> std::io::Result<()> {
+    out.write_all(path.to_string_lossy().as_bytes())
+}
@@ -0,0 +1,107 @@
+//! Output contract of `wdbin __complete`, meant for editor plugins and shell
+//! integrations that need something more stable than the human facing
+//! `complete` output.
+//!
+//! Version 1: the first line is `wd-protocol<TAB>1`, then one line per match,
+//! best first, made of four tab separated fields:
+//!
+//! - score: confidence between 0 and 1, with 4 decimals
+//! - path: absolute path of the match, with backslashes, tabs and newlines
+//!   escaped as `\\`, `\t` and `\n` so a directory name can't split a field
+//!   or a line
+//! - source: `fs` if the input itself is a directory, otherwise the name of
+//!   the candidate source it came from (`history`, `bookmarks`, ...)
+//! - flags: comma separated list of flags, `-` when there is none. `missing`
+//!   means the directory doesn't exist anymore, `denied` that the user can't
+//!   enter it. Unknown flags must be ignored.
+//!
+//! Fields may be added at the end of a line in the same version, so consumers
+//! should ignore any extra field. Any other change bumps the version. With
+//! `--print0`, lines end with a NUL instead of a newline.
+//!
+//! `wdbin __complete-dirs` backs TAB completion in the init scripts: one
+//! absolute path per line (or NUL terminated with `--print0`), best first,
+//! leaving out what the shell's own directory completion already offers for
+//! the same word.
+
+use crate::access;
+use crate::exists::ExistenceCache;
+use crate::messages;
+use crate::output::Output;
+use crate::{CompleteResult, Origin};
+use eyre::bail;
+use std::path::Path;
+
+pub const PROTOCOL_VERSION: u32 = 1;
+
+const fn source_name(source: Origin) -> &'static str {
+    match source {
+        Origin::Filesystem => "fs",
+        Origin::Source(name) => name,
+    }
+}
+
+/// `path` as a protocol field, see the module doc.
+fn field(path: &Path) -> String {
+    path.to_string_lossy()
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+}
+
+fn flags(result: &CompleteResult, cache: &mut ExistenceCache) -> String {
+    let mut flags = vec![];
+    if !cache.is_dir(&result.path) {
+        flags.push("missing");
+    } else if !access::can_enter(&result.path) {
+        flags.push("denied");
+    }
+    if flags.is_empty() {
+        "-".into()
+    } else {
+        flags.join(",")
+    }
+}
+
+pub fn print(
+    protocol: u32,
+    matches: &[CompleteResult],
+    cache: &mut ExistenceCache,
+    print0: bool,
+) -> eyre::Result<()> {
+    if protocol != PROTOCOL_VERSION {
+        bail!(messages::bad_protocol(
+            &protocol.to_string(),
+            &PROTOCOL_VERSION.to_string()
+        ));
+    }
+    let out = Output {
+        print0,
+        human: false,
+    };
+    out.record(&format!("wd-protocol\t{PROTOCOL_VERSION}"))?;
+    for result in matches {
+        out.record(&format!(
+            "{:.4}\t{}\t{}\t{}",
+            result.confidence,
+            field(&result.path),
+            source_name(result.source),
+            flags(result, cache)
+        ))?;
+    }
+    Ok(())
+}
+
+/// Whether the shell's directory completion of `word` from `cwd` would
+/// already offer `path`: it lists the children of the directory `word`
+/// points into.
+pub fn offered_by_shell(word: &str, cwd: &Path, path: &Path) -> bool {
+    let typed_dir = if word.ends_with('/') {
+        Path::new(word)
+    } else {
+        Path::new(word).parent().unwrap_or_else(|| Path::new(""))
+    };
+    let dir = cwd.join(typed_dir);
+    let dir = dir.canonicalize().unwrap_or(dir);
+    path == cwd || path.parent() == Some(dir.as_path())
+}
@@ -0,0 +1,31 @@
+//! Directories wd must never remember, from the `private_paths` config:
+//! nothing under them is written to the db or listed, and whatever was
+//! recorded before is scrubbed on the next write, from the stats too, while
+//! the caches naming them are dropped.
+
+use crate::store::DBContent;
+use std::path::{Path, PathBuf};
+
+pub fn is_private(path: &Path, prefixes: &[PathBuf]) -> bool {
+    prefixes.iter().any(|prefix| path.starts_with(prefix))
+}
+
+/// Drop every trace of private directories from `content`, tombstones
+/// included since they'd still name them. Returns how many entries went.
+pub fn scrub(content: &mut DBContent, prefixes: &[PathBuf]) -> usize {
+    let private = |path: &Path| is_private(path, prefixes);
+    let before = content.paths.len();
+    content.paths.retain(|e| !private(&e.path));
+    content.forgotten.retain(|t| !private(&t.path));
+    content.suppressed.retain(|s| !private(&s.path));
+    content.snoozed.retain(|s| !private(&s.path));
+    content.jumps.retain(|j| !private(&j.path));
+    content
+        .transitions
+        .retain(|t| !private(&t.from) && !private(&t.to));
+    for group in &mut content.groups {
+        group.paths.retain(|p| !private(p));
+    }
+    content.groups.retain(|g| !g.paths.is_empty());
+    before - content.paths.len()
+}
@@ -0,0 +1,79 @@
+//! Finding project directories to seed the database with, so a new machine
+//! doesn't start with an empty history.
+
+use crate::store::{Entry, Store};
+use ignore::WalkBuilder;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// Files or directories found at the root of a project.
+pub const DEFAULT_MARKERS: &[&str] = &[
+    ".git",
+    ".hg",
+    "Cargo.toml",
+    "package.json",
+    "pyproject.toml",
+    "go.mod",
+    "Makefile",
+];
+
+fn is_project(dir: &Path, markers: &[String]) -> bool {
+    markers.iter().any(|marker| dir.join(marker).exists())
+}
+
+/// Directories under `roots`, at most `depth` levels down, containing one of
+/// `markers`. Hidden and gitignored directories are skipped.
+pub fn find(roots: &[PathBuf], depth: usize, markers: &[String]) -> Vec<PathBuf> {
+    let mut found = vec![];
+    for root in roots {
+        let walk = WalkBuilder::new(root)
+            .max_depth(Some(depth))
+            .require_git(false)
+            .filter_entry(|entry| entry.file_type().is_some_and(|t| t.is_dir()))
+            .build();
+        for entry in walk.flatten() {
+            if is_project(entry.path(), markers) {
+                found.extend(entry.path().canonicalize().ok());
+            }
+        }
+    }
+    found
+}
+
+/// Add `projects` the db doesn't know yet, as never visited so they rank
+/// below everything actually used, tagged with `host` if given. Forgotten ones
+/// stay forgotten. Returns how many were added.
+pub fn seed(db: &mut dyn Store, projects: Vec<PathBuf>, host: Option<&str>) -> eyre::Result<usize> {
+    let mut content = db.export()?;
+    let mut known: HashSet<PathBuf> = content
+        .paths
+        .iter()
+        .map(|e| e.path.clone())
+        .chain(content.forgotten.iter().map(|t| t.path.clone()))
+        .collect();
+    let before = content.paths.len();
+    for path in projects {
+        if known.insert(path.clone()) {
+            content.paths.push(Entry {
+                path,
+                last_access: 0,
+                visits: 0,
+                hosts: host.map(str::to_string).into_iter().collect(),
+                id: None,
+            });
+        }
+    }
+    let added = content.paths.len() - before;
+    if added > 0 {
+        db.import(content)?;
+    }
+    Ok(added)
+}
+
+/// Markers from the config, or the default ones.
+pub fn markers(configured: Option<&[String]>) -> Vec<String> {
+    configured.map_or_else(
+        || DEFAULT_MARKERS.iter().map(|m| (*m).to_string()).collect(),
+        <[String]>::to_vec,
+    )
+}
@@ -0,0 +1,50 @@
+//! Driving tmux sessions from matches.
+
+use crate::messages;
+use eyre::{bail, Context};
+use std::path::Path;
+use std::process::Command;
+
+/// tmux session names can't contain `.` or `:`
+fn session_name(path: &Path) -> String {
+    path.file_name()
+        .map_or_else(|| "root".into(), |name| name.to_string_lossy())
+        .replace(['.', ':'], "_")
+}
+
+fn tmux(args: &[&str]) -> eyre::Result<bool> {
+    let status = Command::new("tmux")
+        .args(args)
+        .status()
+        .wrap_err_with(|| messages::couldnt_run("tmux"))?;
+    Ok(status.success())
+}
+
+/// Attach or switch to the session named after `path`, creating it there if
+/// it doesn't exist yet.
+pub fn open(path: &Path) -> eyre::Result<()> {
+    let name = session_name(path);
+    let dir = path.to_string_lossy();
+
+    if std::env::var_os("TMUX").is_none() {
+        if !tmux(&["new-session", "-A", "-s", &name, "-c", &dir])? {
+            bail!(messages::failed("tmux new-session"));
+        }
+        return Ok(());
+    }
+
+    // can't attach from inside tmux, create it detached and switch instead
+    let exists = Command::new("tmux")
+        .args(["has-session", "-t", &format!("={name}")])
+        .stderr(std::process::Stdio::null())
+        .status()
+        .wrap_err_with(|| messages::couldnt_run("tmux"))?
+        .success();
+    if !exists && !tmux(&["new-session", "-d", "-s", &name, "-c", &dir])? {
+        bail!(messages::failed("tmux new-session"));
+    }
+    if !tmux(&["switch-client", "-t", &format!("={name}")])? {
+        bail!(messages::failed("tmux switch-client"));
+    }
+    Ok(())
+}
@@ -0,0 +1,163 @@
+//! Replaying a navigation trace against the ranking, to measure how a
+//! change to the matcher or the config affects real-looking usage. Each step
+//! is ranked from what the previous ones taught the db, then learned as a
+//! jump to its target. There's no library target: this is reached through
+//! the hidden `simulate` subcommand.
+
+use crate::messages;
+use crate::store::Store;
+use eyre::Context;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// A recorded or synthetic session, in the shape of the trace file.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Trace {
+    pub steps: Vec<Step>,
+}
+
+/// The user typed `query` from `from` and meant to go to `target`.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Step {
+    pub query: String,
+    pub target: PathBuf,
+    #[serde(default)]
+    pub from: Option<PathBuf>,
+}
+
+impl Trace {
+    pub fn load(path: &Path) -> eyre::Result<Self> {
+        let raw = std::fs::read_to_string(path).wrap_err_with(|| messages::couldnt_read(path))?;
+        serde_json::from_str(&raw).wrap_err_with(|| messages::invalid_trace(path))
+    }
+}
+
+/// How well the ranking did over a trace.
+#[derive(Debug, Default)]
+pub struct Report {
+    pub steps: usize,
+    pub top1: usize,
+    pub top3: usize,
+    /// sum of 1/rank of the targets, 0 when missing
+    reciprocal_ranks: f64,
+    /// steps whose target wasn't the best match, with its rank if listed
+    pub misses: Vec<(String, PathBuf, Option<usize>)>,
+}
+
+impl Report {
+    fn record(&mut self, step: &Step, rank: Option<usize>) {
+        self.steps += 1;
+        match rank {
+            Some(0) => self.top1 += 1,
+            _ => self
+                .misses
+                .push((step.query.clone(), step.target.clone(), rank)),
+        }
+        if rank.is_some_and(|r| r < 3) {
+            self.top3 += 1;
+        }
+        self.reciprocal_ranks += rank.map_or(0., |r| 1. / (r + 1) as f64);
+    }
+
+    fn percent(&self, count: usize) -> f64 {
+        if self.steps == 0 {
+            return 0.;
+        }
+        100. * count as f64 / self.steps as f64
+    }
+
+    pub fn summary(&self) -> String {
+        format!(
+            "{} steps: top-1 {:.1}%, top-3 {:.1}%, mean reciprocal rank {:.3}",
+            self.steps,
+            self.percent(self.top1),
+            self.percent(self.top3),
+            if self.steps == 0 {
+                0.
+            } else {
+                self.reciprocal_ranks / self.steps as f64
+            }
+        )
+    }
+}
+
+/// Replay `trace` on `db`. `rank` returns the matches for a step, best
+/// first, as the real lookup would from the db's current content; the
+/// target is then learned like a jump.
+pub fn replay(
+    trace: &Trace,
+    db: &mut dyn Store,
+    mut rank: impl FnMut(&dyn Store, &Step) -> eyre::Result<Vec<PathBuf>>,
+) -> eyre::Result<Report> {
+    let mut report = Report::default();
+    for step in &trace.steps {
+        let matches = rank(db, step)?;
+        report.record(step, matches.iter().position(|path| *path == step.target));
+        if let Some(from) = &step.from {
+            if *from != step.target {
+                db.record_transition(from.clone(), step.target.clone())?;
+            }
+        }
+        db.bump(step.target.clone(), None)?;
+    }
+    Ok(report)
+}
+
+#[cfg(all(test, feature = "simulate"))]
+mod tests {
+    use super::*;
+    use crate::matcher::rank;
+    use crate::store::MemoryStore;
+
+    fn step(query: &str, target: &str, from: Option<&str>) -> Step {
+        Step {
+            query: query.to_string(),
+            target: PathBuf::from(target),
+            from: from.map(PathBuf::from),
+        }
+    }
+
+    #[test]
+    fn each_step_is_ranked_before_it_is_learned() {
+        let trace = Trace {
+            steps: vec![
+                step("api", "/w/acme/api", Some("/home")),
+                step("api", "/w/acme/api", Some("/home")),
+                step("web", "/w/acme/web", Some("/w/acme/api")),
+                step("acme", "/w/acme/api", None),
+            ],
+        };
+        let mut db = MemoryStore::default();
+        let mut seen = vec![];
+        let report = replay(&trace, &mut db, |db, step| {
+            let entries = db.entries()?;
+            seen.push(entries.len());
+            Ok(rank(&entries, &step.query, 0., &[])?
+                .into_iter()
+                .map(|(_, path)| path.clone())
+                .collect())
+        })
+        .unwrap();
+
+        // the ranking only sees the steps before the current one
+        assert_eq!(seen, [0, 1, 1, 2]);
+        assert_eq!(report.steps, 4);
+        // a first visit can't be found, a second one comes first, and the
+        // most recent of two matches wins
+        let misses: Vec<_> = report
+            .misses
+            .iter()
+            .map(|(query, _, rank)| (query.as_str(), *rank))
+            .collect();
+        assert_eq!(misses, [("api", None), ("web", None), ("acme", Some(1))]);
+        assert_eq!((report.top1, report.top3), (1, 2));
+        // targets are learned as jumps, along with where they came from
+        let order: Vec<_> = db.entries().unwrap().into_iter().map(|e| e.path).collect();
+        assert_eq!(order, [PathBuf::from("/w/acme/api"), "/w/acme/web".into()]);
+        let from_api = db.transitions_from(Path::new("/w/acme/api")).unwrap();
+        assert_eq!(from_api.len(), 1);
+        assert_eq!(from_api[0].to, Path::new("/w/acme/web"));
+    }
+}
@@ -0,0 +1,29 @@
+use super::{unvisited, Context, Source};
+use crate::store::Entry;
+use std::path::PathBuf;
+
+/// Directories listed in the config, whether they were visited or not.
+pub struct Bookmarks {
+    paths: Vec<PathBuf>,
+}
+
+impl Bookmarks {
+    pub const fn new(paths: Vec<PathBuf>) -> Self {
+        Self { paths }
+    }
+}
+
+impl Source for Bookmarks {
+    fn name(&self) -> &'static str {
+        "bookmarks"
+    }
+
+    fn candidates(&self, _ctx: &Context) -> eyre::Result<Vec<Entry>> {
+        Ok(self
+            .paths
+            .iter()
+            .filter_map(|path| path.canonicalize().ok())
+            .map(unvisited)
+            .collect())
+    }
+}
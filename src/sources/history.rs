@@ -0,0 +1,24 @@
+use super::{Context, Source};
+use crate::store::Entry;
+
+/// Directories jumped to before. When entries are tagged with hosts, the ones
+/// only visited on other machines are left dormant.
+pub struct History;
+
+impl Source for History {
+    fn name(&self) -> &'static str {
+        "history"
+    }
+
+    fn candidates(&self, ctx: &Context) -> eyre::Result<Vec<Entry>> {
+        let mut entries = ctx.db.entries()?;
+        if let Some(host) = ctx.host {
+            entries.retain(|e| e.hosts.is_empty() || e.hosts.iter().any(|h| h == host));
+        }
+        Ok(entries)
+    }
+
+    fn by_recency(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,97 @@
+//! Where candidates come from. Each enabled source lists directories, which
+//! are scored against the query and merged, scaled by the source's weight.
+
+use crate::config::SourcesConfig;
+use crate::store::{Entry, Store};
+use std::path::{Path, PathBuf};
+
+mod bookmarks;
+mod history;
+mod repos;
+mod scan;
+mod worktrees;
+
+pub use bookmarks::Bookmarks;
+pub use history::History;
+pub use repos::Repos;
+pub use scan::Scan;
+pub use worktrees::Worktrees;
+
+/// Names of every source.
+pub const NAMES: &[&str] = &["history", "bookmarks", "scan", "repos", "worktrees"];
+
+/// The source called `name`, as a `'static` string.
+pub fn static_name(name: &str) -> Option<&'static str> {
+    NAMES.iter().copied().find(|n| *n == name)
+}
+
+/// What sources can look at to find candidates.
+pub struct Context<'a> {
+    pub db: &'a dyn Store,
+    /// directory the jump starts from
+    pub from: Option<&'a Path>,
+    /// this machine, when entries are tagged with hosts
+    pub host: Option<&'a str>,
+}
+
+pub trait Source {
+    /// Name in the config and in the `__complete` output.
+    fn name(&self) -> &'static str;
+
+    fn candidates(&self, ctx: &Context) -> eyre::Result<Vec<Entry>>;
+
+    /// Whether candidates come most recently used first, in which case the
+    /// position weighs in their score.
+    fn by_recency(&self) -> bool {
+        false
+    }
+}
+
+/// Candidate with no history attached.
+pub const fn unvisited(path: PathBuf) -> Entry {
+    Entry {
+        path,
+        last_access: 0,
+        visits: 0,
+        hosts: vec![],
+        id: None,
+    }
+}
+
+/// Enabled sources with their weight. `repos_cache` is where the `repos`
+/// source may cache directory listings.
+pub fn enabled(
+    config: &SourcesConfig,
+    repos_cache: Option<PathBuf>,
+) -> Vec<(Box<dyn Source>, f64)> {
+    let mut sources: Vec<(Box<dyn Source>, f64)> = vec![];
+    if config.history.enabled {
+        sources.push((Box::new(History), config.history.weight));
+    }
+    if config.bookmarks.enabled && !config.bookmarks.paths.is_empty() {
+        sources.push((
+            Box::new(Bookmarks::new(config.bookmarks.paths.clone())),
+            config.bookmarks.weight,
+        ));
+    }
+    if config.scan.enabled {
+        sources.push((
+            Box::new(Scan::new(config.scan.roots.clone(), config.scan.depth)),
+            config.scan.weight,
+        ));
+    }
+    if config.repos.enabled && !config.repos.roots.is_empty() {
+        sources.push((
+            Box::new(Repos::new(
+                config.repos.roots.clone(),
+                config.repos.depth,
+                repos_cache,
+            )),
+            config.repos.weight,
+        ));
+    }
+    if config.worktrees.enabled {
+        sources.push((Box::new(Worktrees), config.worktrees.weight));
+    }
+    sources
+}
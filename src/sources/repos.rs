@@ -0,0 +1,145 @@
+use super::{unvisited, Context, Source};
+use crate::messages;
+use crate::store::{self, Entry};
+use eyre::Context as _;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Git repositories and worktrees under some roots, so freshly cloned ones
+/// can be jumped to before ever visiting them.
+pub struct Repos {
+    roots: Vec<PathBuf>,
+    depth: usize,
+    /// where listings are cached, `None` to always read the directories
+    cache_path: Option<PathBuf>,
+}
+
+/// What a directory contained the last time it was read. Adding or removing
+/// a child changes the directory's mtime, which invalidates it.
+#[derive(Debug, Serialize, Deserialize)]
+struct Listing {
+    mtime: u128,
+    is_repo: bool,
+    children: Vec<PathBuf>,
+}
+
+impl Repos {
+    pub const fn new(roots: Vec<PathBuf>, depth: usize, cache_path: Option<PathBuf>) -> Self {
+        Self {
+            roots,
+            depth,
+            cache_path,
+        }
+    }
+
+    fn load_cache(&self) -> HashMap<PathBuf, Listing> {
+        let Some(path) = &self.cache_path else {
+            return HashMap::new();
+        };
+        let Ok(raw) = std::fs::read(path) else {
+            return HashMap::new();
+        };
+        serde_json::from_slice(&raw).unwrap_or_else(|err| {
+            eprintln!(
+                "{}",
+                messages::ignoring_unreadable(&path.display().to_string(), &err.into())
+            );
+            HashMap::new()
+        })
+    }
+
+    fn write_cache(&self, cache: &HashMap<PathBuf, Listing>) -> eyre::Result<()> {
+        let Some(path) = &self.cache_path else {
+            return Ok(());
+        };
+        store::write_private(path, &serde_json::to_vec(cache)?)
+            .wrap_err_with(|| messages::error_writing(path))
+    }
+}
+
+fn mtime(dir: &Path) -> Option<u128> {
+    let modified = dir.metadata().ok()?.modified().ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_nanos())
+}
+
+fn read_listing(dir: &Path, mtime: u128) -> Option<Listing> {
+    let mut is_repo = false;
+    let mut children = vec![];
+    for child in std::fs::read_dir(dir).ok()?.flatten() {
+        let name = child.file_name();
+        // a `.git` file is a linked worktree
+        if name == ".git" {
+            is_repo = true;
+        } else if !name.to_string_lossy().starts_with('.')
+            && child.file_type().is_ok_and(|t| t.is_dir())
+        {
+            children.push(child.path());
+        }
+    }
+    Some(Listing {
+        mtime,
+        is_repo,
+        children,
+    })
+}
+
+/// Walk `dir`, reusing cached listings that are still fresh. Returns whether
+/// the cache changed.
+fn walk(
+    dir: &Path,
+    depth: usize,
+    cache: &mut HashMap<PathBuf, Listing>,
+    found: &mut Vec<Entry>,
+) -> bool {
+    let Some(mtime) = mtime(dir) else {
+        return cache.remove(dir).is_some();
+    };
+    let mut changed = cache.get(dir).is_none_or(|listing| listing.mtime != mtime);
+    if changed {
+        match read_listing(dir, mtime) {
+            Some(listing) => cache.insert(dir.to_path_buf(), listing),
+            None => return cache.remove(dir).is_some(),
+        };
+    }
+    let listing = &cache[dir];
+    if listing.is_repo {
+        // nested repositories are rare enough not to be worth the walk
+        found.push(unvisited(dir.to_path_buf()));
+        return changed;
+    }
+    if depth == 0 {
+        return changed;
+    }
+    for child in listing.children.clone() {
+        changed |= walk(&child, depth - 1, cache, found);
+    }
+    changed
+}
+
+impl Source for Repos {
+    fn name(&self) -> &'static str {
+        "repos"
+    }
+
+    fn candidates(&self, _ctx: &Context) -> eyre::Result<Vec<Entry>> {
+        let mut cache = self.load_cache();
+        let mut found = vec![];
+        let mut changed = false;
+        for root in self
+            .roots
+            .iter()
+            .filter_map(|root| root.canonicalize().ok())
+        {
+            changed |= walk(&root, self.depth, &mut cache, &mut found);
+        }
+        if changed {
+            // the cache only saves time, a jump shouldn't fail over it
+            if let Err(err) = self.write_cache(&cache) {
+                eprintln!("{}", messages::couldnt_write("repository cache", &err));
+            }
+        }
+        Ok(found)
+    }
+}
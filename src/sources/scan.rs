@@ -0,0 +1,56 @@
+use super::{unvisited, Context, Source};
+use crate::store::Entry;
+use std::path::{Path, PathBuf};
+
+/// Subdirectories of some roots, the directory the jump starts from if none
+/// are configured. Hidden directories are skipped.
+pub struct Scan {
+    roots: Vec<PathBuf>,
+    depth: usize,
+}
+
+impl Scan {
+    pub const fn new(roots: Vec<PathBuf>, depth: usize) -> Self {
+        Self { roots, depth }
+    }
+}
+
+fn walk(dir: &Path, depth: usize, found: &mut Vec<Entry>) {
+    if depth == 0 {
+        return;
+    }
+    let Ok(children) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for child in children.flatten() {
+        let hidden = child.file_name().to_string_lossy().starts_with('.');
+        if hidden || !child.file_type().is_ok_and(|t| t.is_dir()) {
+            continue;
+        }
+        let path = child.path();
+        walk(&path, depth - 1, found);
+        found.push(unvisited(path));
+    }
+}
+
+impl Source for Scan {
+    fn name(&self) -> &'static str {
+        "scan"
+    }
+
+    fn candidates(&self, ctx: &Context) -> eyre::Result<Vec<Entry>> {
+        let roots: Vec<PathBuf> = if self.roots.is_empty() {
+            ctx.from.map(Path::to_path_buf).into_iter().collect()
+        } else {
+            self.roots
+                .iter()
+                .filter_map(|root| root.canonicalize().ok())
+                .collect()
+        };
+        let mut found = vec![];
+        for root in roots {
+            walk(&root, self.depth, &mut found);
+        }
+        Ok(found)
+    }
+}
@@ -0,0 +1,37 @@
+use super::{unvisited, Context, Source};
+use crate::store::Entry;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+/// Worktrees of the git repository the jump starts from.
+pub struct Worktrees;
+
+impl Source for Worktrees {
+    fn name(&self) -> &'static str {
+        "worktrees"
+    }
+
+    fn candidates(&self, ctx: &Context) -> eyre::Result<Vec<Entry>> {
+        let Some(from) = ctx.from else {
+            return Ok(vec![]);
+        };
+        // not being in a repository, or not having git, just means no
+        // worktrees to offer
+        let Ok(output) = Command::new("git")
+            .args(["worktree", "list", "--porcelain"])
+            .current_dir(from)
+            .stderr(Stdio::null())
+            .output()
+        else {
+            return Ok(vec![]);
+        };
+        if !output.status.success() {
+            return Ok(vec![]);
+        }
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| line.strip_prefix("worktree "))
+            .map(|path| unvisited(PathBuf::from(path)))
+            .collect())
+    }
+}
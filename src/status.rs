@@ -0,0 +1,80 @@
+//! What wd knows about a directory, for prompts.
+//!
+//! `wdbin status --porcelain` prints a single line of four tab separated
+//! fields, meant for prompt frameworks like starship's custom modules:
+//!
+//! - rank: position of the directory in the history, most recent being 1,
+//!   `-` if it was never visited
+//! - visits: how many times it was jumped to
+//! - last access: unix timestamp of the last visit, 0 if unknown
+//! - flags: comma separated list, `-` when there is none. `pinned` means the
+//!   directory is bookmarked in the config, `snoozed` that it's left out of
+//!   matches for a while, `dormant` that it was only visited on other hosts.
+//!   Unknown flags must be ignored.
+//!
+//! Fields may be added at the end of the line, so consumers should ignore any
+//! extra field. Any other change will be announced as a breaking change.
+
+use crate::output;
+use crate::store::Entry;
+use crate::time;
+use std::path::Path;
+
+pub struct Status<'a> {
+    pub path: &'a Path,
+    /// 0 based position in the history and the entry there
+    pub entry: Option<(usize, Entry)>,
+    pub pinned: bool,
+    pub snoozed: bool,
+    pub dormant: bool,
+}
+
+impl Status<'_> {
+    fn flags(&self) -> Vec<&'static str> {
+        [
+            (self.pinned, "pinned"),
+            (self.snoozed, "snoozed"),
+            (self.dormant, "dormant"),
+        ]
+        .iter()
+        .filter_map(|&(set, flag)| set.then_some(flag))
+        .collect()
+    }
+
+    pub fn porcelain(&self) -> String {
+        let flags = self.flags();
+        let flags = if flags.is_empty() {
+            "-".to_string()
+        } else {
+            flags.join(",")
+        };
+        match &self.entry {
+            Some((rank, entry)) => format!(
+                "{}\t{}\t{}\t{flags}",
+                rank + 1,
+                entry.visits,
+                entry.last_access
+            ),
+            None => format!("-\t0\t0\t{flags}"),
+        }
+    }
+
+    pub fn human(&self) -> String {
+        let mut out = output::escape(self.path).into_owned();
+        match &self.entry {
+            Some((rank, entry)) => {
+                out += &format!(": #{}, {} visits", rank + 1, entry.visits);
+                if entry.last_access > 0 {
+                    let ago = crate::store::now().saturating_sub(entry.last_access);
+                    out += &format!(", last {} ago", time::format_duration(ago));
+                }
+            }
+            None => out += ": never visited",
+        }
+        for flag in self.flags() {
+            out += ", ";
+            out += flag;
+        }
+        out
+    }
+}
@@ -0,0 +1,88 @@
+use super::{load_json, DBContent, Entry, Group, Jump, Snooze, Store, Suppression, Transition};
+use crate::messages;
+use eyre::Context;
+use std::path::{Path, PathBuf};
+
+/// The whole database as a single json file, rewritten on flush.
+pub struct JsonStore {
+    file_path: PathBuf,
+    content: DBContent,
+}
+
+impl JsonStore {
+    pub fn open(file_path: PathBuf) -> eyre::Result<Self> {
+        let content = load_json(&file_path)?;
+        Ok(Self { file_path, content })
+    }
+}
+
+impl Store for JsonStore {
+    fn entries(&self) -> eyre::Result<Vec<Entry>> {
+        Ok(self.content.paths.clone())
+    }
+
+    fn bump(&mut self, path: PathBuf, host: Option<&str>) -> eyre::Result<()> {
+        self.content.bump(path, host);
+        Ok(())
+    }
+
+    fn forget(&mut self, path: PathBuf) -> eyre::Result<()> {
+        self.content.forget(path);
+        Ok(())
+    }
+
+    fn suppressions(&self) -> eyre::Result<Vec<Suppression>> {
+        Ok(self.content.suppressed.clone())
+    }
+
+    fn suppress(&mut self, suppression: Suppression) -> eyre::Result<()> {
+        self.content.suppress(suppression);
+        Ok(())
+    }
+
+    fn snoozed(&self) -> eyre::Result<Vec<Snooze>> {
+        Ok(self.content.snoozed())
+    }
+
+    fn snooze(&mut self, path: PathBuf, until: u64) -> eyre::Result<()> {
+        self.content.snooze(path, until);
+        Ok(())
+    }
+
+    fn jumps(&self) -> eyre::Result<Vec<Jump>> {
+        Ok(self.content.jumps.clone())
+    }
+
+    fn transitions_from(&self, from: &Path) -> eyre::Result<Vec<Transition>> {
+        Ok(self.content.transitions_from(from))
+    }
+
+    fn record_transition(&mut self, from: PathBuf, to: PathBuf) -> eyre::Result<()> {
+        self.content.record_transition(from, to);
+        Ok(())
+    }
+
+    fn groups(&self) -> eyre::Result<Vec<Group>> {
+        Ok(self.content.groups.clone())
+    }
+
+    fn set_group(&mut self, group: Group) -> eyre::Result<()> {
+        self.content.set_group(group);
+        Ok(())
+    }
+
+    fn export(&self) -> eyre::Result<DBContent> {
+        Ok(self.content.clone())
+    }
+
+    fn import(&mut self, content: DBContent) -> eyre::Result<()> {
+        self.content = content;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> eyre::Result<()> {
+        self.content
+            .write_to(&self.file_path)
+            .wrap_err_with(|| messages::error_writing(&self.file_path))
+    }
+}
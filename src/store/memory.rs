@@ -0,0 +1,90 @@
+use super::{load_json, DBContent, Entry, Group, Jump, Snooze, Store, Suppression, Transition};
+use std::path::{Path, PathBuf};
+
+/// Keeps everything in memory and never persists anything.
+#[derive(Default)]
+pub struct MemoryStore {
+    content: DBContent,
+}
+
+impl MemoryStore {
+    pub const fn new(content: DBContent) -> Self {
+        Self { content }
+    }
+
+    /// Start from the content of a json database, without ever writing to it.
+    pub fn seeded_from(file_path: &Path) -> eyre::Result<Self> {
+        Ok(Self {
+            content: load_json(file_path)?,
+        })
+    }
+}
+
+impl Store for MemoryStore {
+    fn entries(&self) -> eyre::Result<Vec<Entry>> {
+        Ok(self.content.paths.clone())
+    }
+
+    fn bump(&mut self, path: PathBuf, host: Option<&str>) -> eyre::Result<()> {
+        self.content.bump(path, host);
+        Ok(())
+    }
+
+    fn forget(&mut self, path: PathBuf) -> eyre::Result<()> {
+        self.content.forget(path);
+        Ok(())
+    }
+
+    fn suppressions(&self) -> eyre::Result<Vec<Suppression>> {
+        Ok(self.content.suppressed.clone())
+    }
+
+    fn suppress(&mut self, suppression: Suppression) -> eyre::Result<()> {
+        self.content.suppress(suppression);
+        Ok(())
+    }
+
+    fn snoozed(&self) -> eyre::Result<Vec<Snooze>> {
+        Ok(self.content.snoozed())
+    }
+
+    fn snooze(&mut self, path: PathBuf, until: u64) -> eyre::Result<()> {
+        self.content.snooze(path, until);
+        Ok(())
+    }
+
+    fn jumps(&self) -> eyre::Result<Vec<Jump>> {
+        Ok(self.content.jumps.clone())
+    }
+
+    fn transitions_from(&self, from: &Path) -> eyre::Result<Vec<Transition>> {
+        Ok(self.content.transitions_from(from))
+    }
+
+    fn record_transition(&mut self, from: PathBuf, to: PathBuf) -> eyre::Result<()> {
+        self.content.record_transition(from, to);
+        Ok(())
+    }
+
+    fn groups(&self) -> eyre::Result<Vec<Group>> {
+        Ok(self.content.groups.clone())
+    }
+
+    fn set_group(&mut self, group: Group) -> eyre::Result<()> {
+        self.content.set_group(group);
+        Ok(())
+    }
+
+    fn export(&self) -> eyre::Result<DBContent> {
+        Ok(self.content.clone())
+    }
+
+    fn import(&mut self, content: DBContent) -> eyre::Result<()> {
+        self.content = content;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> eyre::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,500 @@
+use crate::config::{Config, StoreKind};
+use crate::error::WdError;
+use dirs::data_dir;
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, Error as IOError, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+mod json;
+mod memory;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+pub use json::JsonStore;
+pub use memory::MemoryStore;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteStore;
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Device and inode of a directory, which survive renames and moves
+/// within the same filesystem.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct FileId {
+    pub dev: u64,
+    pub ino: u64,
+}
+
+impl FileId {
+    #[cfg(unix)]
+    pub fn of(path: &Path) -> Option<Self> {
+        use std::os::unix::fs::MetadataExt;
+        let metadata = std::fs::metadata(path).ok()?;
+        Some(Self {
+            dev: metadata.dev(),
+            ino: metadata.ino(),
+        })
+    }
+
+    #[cfg(not(unix))]
+    pub fn of(_path: &Path) -> Option<Self> {
+        None
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(from = "EntryRepr")]
+pub struct Entry {
+    pub path: PathBuf,
+    pub last_access: u64,
+    pub visits: u64,
+    /// machines the entry was visited on, empty if it's valid on any of them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hosts: Vec<String>,
+    /// the directory's identity when last visited, only meaningful on the
+    /// machine that recorded it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<FileId>,
+}
+
+// databases written before entries carried metadata only stored bare paths
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum EntryRepr {
+    Legacy(PathBuf),
+    Full {
+        path: PathBuf,
+        #[serde(default)]
+        last_access: u64,
+        #[serde(default)]
+        visits: u64,
+        #[serde(default)]
+        hosts: Vec<String>,
+        #[serde(default)]
+        id: Option<FileId>,
+    },
+}
+
+impl From<EntryRepr> for Entry {
+    fn from(repr: EntryRepr) -> Self {
+        match repr {
+            EntryRepr::Legacy(path) => Self {
+                path,
+                last_access: 0,
+                visits: 0,
+                hosts: vec![],
+                id: None,
+            },
+            EntryRepr::Full {
+                path,
+                last_access,
+                visits,
+                hosts,
+                id,
+            } => Self {
+                path,
+                last_access,
+                visits,
+                hosts,
+                id,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Tombstone {
+    pub path: PathBuf,
+    pub at: u64,
+}
+
+/// `path` is never a match for `query`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Suppression {
+    pub query: String,
+    pub path: PathBuf,
+}
+
+/// `path` is left out of matches until `until`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Snooze {
+    pub path: PathBuf,
+    pub until: u64,
+}
+
+/// A visit to `path` at `at`, in the jump log.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Jump {
+    pub at: u64,
+    pub path: PathBuf,
+}
+
+/// How many jumps the log keeps, older ones are dropped.
+pub const JUMP_LOG_SIZE: usize = 1000;
+
+/// How many times a jump went from `from` to `to`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Transition {
+    pub from: PathBuf,
+    pub to: PathBuf,
+    pub count: u64,
+}
+
+/// Directories cycled through with `wd next` and `wd prev`, in order.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Group {
+    pub name: String,
+    pub paths: Vec<PathBuf>,
+    /// when it was last changed, for syncing
+    #[serde(default)]
+    pub edited: u64,
+}
+
+/// The group `name` was removed at `at`, so syncing doesn't bring back an
+/// older copy of it.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RemovedGroup {
+    pub name: String,
+    pub at: u64,
+}
+
+/// Everything a store holds, in the shape of the json database file.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct DBContent {
+    pub paths: Vec<Entry>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub forgotten: Vec<Tombstone>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub suppressed: Vec<Suppression>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub transitions: Vec<Transition>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub snoozed: Vec<Snooze>,
+    /// oldest first, at most `JUMP_LOG_SIZE`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub jumps: Vec<Jump>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<Group>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub removed_groups: Vec<RemovedGroup>,
+}
+
+impl DBContent {
+    pub const fn new() -> Self {
+        Self {
+            paths: vec![],
+            forgotten: vec![],
+            suppressed: vec![],
+            transitions: vec![],
+            snoozed: vec![],
+            jumps: vec![],
+            groups: vec![],
+            removed_groups: vec![],
+        }
+    }
+
+    pub fn load(file_path: &Path) -> Result<Option<Self>, IOError> {
+        match File::open(file_path) {
+            Ok(file) => Ok(Some(serde_json::from_reader(BufReader::new(file))?)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Replace the db at `file_path` all at once, so a crash or a full disk
+    /// midway leaves the previous content rather than a truncated file.
+    pub fn write_to(&self, file_path: &Path) -> Result<(), IOError> {
+        write_private(file_path, &serde_json::to_vec(self)?)
+    }
+
+    pub fn bump(&mut self, path: PathBuf, host: Option<&str>) {
+        let (visits, mut hosts, id) = self
+            .paths
+            .iter()
+            .find(|e| e.path == path)
+            .map_or((0, vec![], None), |e| (e.visits, e.hosts.clone(), e.id));
+        if let Some(host) = host {
+            if !hosts.iter().any(|h| h == host) {
+                hosts.push(host.to_string());
+            }
+        }
+        self.paths.retain(|e| e.path != path);
+        self.forgotten.retain(|t| t.path != path);
+        let at = now();
+        self.jumps.push(Jump {
+            at,
+            path: path.clone(),
+        });
+        if self.jumps.len() > JUMP_LOG_SIZE {
+            self.jumps.drain(..self.jumps.len() - JUMP_LOG_SIZE);
+        }
+        let id = FileId::of(&path).or(id);
+        self.paths.insert(
+            0,
+            Entry {
+                path,
+                last_access: at,
+                visits: visits + 1,
+                hosts,
+                id,
+            },
+        );
+    }
+
+    pub fn forget(&mut self, path: PathBuf) {
+        self.paths.retain(|e| e.path != path);
+        self.forgotten.retain(|t| t.path != path);
+        self.forgotten.push(Tombstone { path, at: now() });
+    }
+
+    pub fn transitions_from(&self, from: &Path) -> Vec<Transition> {
+        self.transitions
+            .iter()
+            .filter(|t| t.from == from)
+            .cloned()
+            .collect()
+    }
+
+    pub fn record_transition(&mut self, from: PathBuf, to: PathBuf) {
+        match self
+            .transitions
+            .iter_mut()
+            .find(|t| t.from == from && t.to == to)
+        {
+            Some(transition) => transition.count += 1,
+            None => self.transitions.push(Transition { from, to, count: 1 }),
+        }
+    }
+
+    pub fn suppress(&mut self, suppression: Suppression) {
+        if !self.suppressed.contains(&suppression) {
+            self.suppressed.push(suppression);
+        }
+    }
+
+    /// Snoozes still in effect.
+    pub fn snoozed(&self) -> Vec<Snooze> {
+        let now = now();
+        self.snoozed
+            .iter()
+            .filter(|s| s.until > now)
+            .cloned()
+            .collect()
+    }
+
+    /// Replace the group of the same name, or remove it if `group` has no
+    /// paths, remembering when either happened.
+    pub fn set_group(&mut self, mut group: Group) {
+        let at = now();
+        self.groups.retain(|g| g.name != group.name);
+        self.removed_groups.retain(|r| r.name != group.name);
+        if group.paths.is_empty() {
+            self.removed_groups.push(RemovedGroup {
+                name: group.name,
+                at,
+            });
+            self.removed_groups.sort_by(|a, b| a.name.cmp(&b.name));
+        } else {
+            group.edited = at;
+            self.groups.push(group);
+            self.groups.sort_by(|a, b| a.name.cmp(&b.name));
+        }
+    }
+
+    /// Snooze `path`, replacing any previous snooze and dropping expired ones.
+    pub fn snooze(&mut self, path: PathBuf, until: u64) {
+        let now = now();
+        self.snoozed.retain(|s| s.path != path && s.until > now);
+        self.snoozed.push(Snooze { path, until });
+    }
+}
+
+/// Where jump history lives. Changes may be buffered until `flush`.
+pub trait Store {
+    /// Every entry, most recently visited first.
+    fn entries(&self) -> eyre::Result<Vec<Entry>>;
+
+    /// Record a visit to `path` in the entries and the jump log, tagging it
+    /// with `host` if given.
+    fn bump(&mut self, path: PathBuf, host: Option<&str>) -> eyre::Result<()>;
+
+    fn forget(&mut self, path: PathBuf) -> eyre::Result<()>;
+
+    fn suppressions(&self) -> eyre::Result<Vec<Suppression>>;
+
+    fn suppress(&mut self, suppression: Suppression) -> eyre::Result<()>;
+
+    /// Snoozes that haven't expired yet.
+    fn snoozed(&self) -> eyre::Result<Vec<Snooze>>;
+
+    /// Leave `path` out of matches until the `until` timestamp.
+    fn snooze(&mut self, path: PathBuf, until: u64) -> eyre::Result<()>;
+
+    /// The jump log, most recent last.
+    fn jumps(&self) -> eyre::Result<Vec<Jump>>;
+
+    /// Jumps previously made from `from`.
+    fn transitions_from(&self, from: &Path) -> eyre::Result<Vec<Transition>>;
+
+    fn record_transition(&mut self, from: PathBuf, to: PathBuf) -> eyre::Result<()>;
+
+    /// Every group, by name.
+    fn groups(&self) -> eyre::Result<Vec<Group>>;
+
+    /// Replace the group of the same name, or remove it if `group` has no
+    /// paths.
+    fn set_group(&mut self, group: Group) -> eyre::Result<()>;
+
+    /// The full content, including tombstones, for whole-database operations
+    /// like syncing.
+    fn export(&self) -> eyre::Result<DBContent>;
+
+    /// Replace the full content of the store.
+    fn import(&mut self, content: DBContent) -> eyre::Result<()>;
+
+    fn flush(&mut self) -> eyre::Result<()>;
+}
+
+fn default_db_path(kind: StoreKind, profile: Option<&str>) -> PathBuf {
+    let mut a = data_dir().unwrap_or_else(|| "/tmp/".into());
+    match (kind, profile) {
+        (StoreKind::Sqlite, None) => a.push("wd/wd.sqlite"),
+        (StoreKind::Sqlite, Some(profile)) => a.push(format!("wd/wd-{profile}.sqlite")),
+        (StoreKind::Json | StoreKind::Memory, None) => a.push("wd/wddb"),
+        (StoreKind::Json | StoreKind::Memory, Some(profile)) => {
+            a.push(format!("wd/wddb-{profile}"));
+        }
+    }
+    a
+}
+
+/// Location of the database: `--db`, then the config, then the default for
+/// the configured store and profile.
+pub fn db_file_path(db_path: Option<&Path>, config: &Config) -> PathBuf {
+    db_path.or(config.db_path.as_deref()).map_or_else(
+        || default_db_path(config.store, config.profile.as_deref()),
+        Path::to_path_buf,
+    )
+}
+
+/// Path of a file living next to the database, named after it.
+pub fn sidecar_path(db_path: Option<&Path>, config: &Config, suffix: &str) -> PathBuf {
+    let mut path = db_file_path(db_path, config).into_os_string();
+    path.push(".");
+    path.push(suffix);
+    path.into()
+}
+
+/// Options creating a file only its owner can read and write, the db holding
+/// where the user goes. Existing files keep their permissions.
+fn private_options() -> OpenOptions {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options
+}
+
+/// Replace `file_path` with `content` through a private temporary file next
+/// to it, so readers see either the old or the new content, never half of
+/// it, and the db and the sidecars holding queries and visited paths end up
+/// readable by their owner only, including ones written before they were
+/// private.
+pub fn write_private(file_path: &Path, content: &[u8]) -> Result<(), IOError> {
+    let mut name = file_path.file_name().unwrap_or_default().to_owned();
+    name.push(format!(".{}.tmp", std::process::id()));
+    let tmp = file_path.with_file_name(name);
+    // left behind by a crashed process with the same pid
+    let _ = std::fs::remove_file(&tmp);
+    let written = private_options()
+        .create_new(true)
+        .open(&tmp)
+        .and_then(|mut file| file.write_all(content))
+        .and_then(|()| std::fs::rename(&tmp, file_path));
+    if written.is_err() {
+        let _ = std::fs::remove_file(&tmp);
+    }
+    written
+}
+
+/// Create `file_path` empty and private if it doesn't exist, for stores
+/// whose library would create it with default permissions.
+#[cfg_attr(not(feature = "sqlite"), allow(dead_code))]
+fn create_private(file_path: &Path) -> Result<(), IOError> {
+    match private_options().create_new(true).open(file_path) {
+        Err(e) if e.kind() != ErrorKind::AlreadyExists => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// Refuse a database other users can write to: what it holds ends up
+/// eval'd by the shell, unless the config allows it.
+fn check_permissions(file_path: &Path, config: &Config) -> eyre::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let writable_by_others = std::fs::metadata(file_path)
+            .is_ok_and(|metadata| metadata.permissions().mode() & 0o022 != 0);
+        if writable_by_others && !config.allow_shared_db {
+            return Err(WdError::DbInsecure {
+                path: file_path.to_path_buf(),
+            }
+            .into());
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = (file_path, config);
+    Ok(())
+}
+
+pub fn open(db_path: Option<&Path>, config: &Config) -> eyre::Result<Box<dyn Store>> {
+    let kind = config.store;
+    let file_path = db_file_path(db_path, config);
+    check_permissions(&file_path, config)?;
+
+    let store: Box<dyn Store> = match kind {
+        StoreKind::Json => Box::new(JsonStore::open(file_path)?),
+        StoreKind::Memory => Box::new(MemoryStore::seeded_from(&file_path)?),
+        #[cfg(feature = "sqlite")]
+        StoreKind::Sqlite => Box::new(SqliteStore::open(&file_path)?),
+        #[cfg(not(feature = "sqlite"))]
+        StoreKind::Sqlite => {
+            eyre::bail!(crate::messages::no_sqlite())
+        }
+    };
+    Ok(store)
+}
+
+/// The content of the configured store in memory, never writing anything nor
+/// creating the database if it doesn't exist.
+pub fn open_read_only(db_path: Option<&Path>, config: &Config) -> eyre::Result<Box<dyn Store>> {
+    let file_path = db_file_path(db_path, config);
+    check_permissions(&file_path, config)?;
+    let content = match config.store {
+        StoreKind::Json | StoreKind::Memory => load_json(&file_path)?,
+        StoreKind::Sqlite if !file_path.exists() => DBContent::new(),
+        #[cfg(feature = "sqlite")]
+        StoreKind::Sqlite => SqliteStore::open_read_only(&file_path)?.export()?,
+        #[cfg(not(feature = "sqlite"))]
+        StoreKind::Sqlite => {
+            eyre::bail!(crate::messages::no_sqlite())
+        }
+    };
+    Ok(Box::new(MemoryStore::new(content)))
+}
+
+pub fn load_json(file_path: &Path) -> eyre::Result<DBContent> {
+    Ok(DBContent::load(file_path)
+        .map_err(|err| WdError::loading(file_path.to_path_buf(), err))?
+        .unwrap_or_else(DBContent::new))
+}
@@ -0,0 +1,603 @@
+use super::{
+    create_private, now, DBContent, Entry, FileId, Group, Jump, RemovedGroup, Snooze, Store,
+    Suppression, Tombstone, Transition, JUMP_LOG_SIZE,
+};
+use crate::error::WdError;
+use crate::messages;
+use eyre::Context;
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ValueRef};
+use rusqlite::{params, Connection, ErrorCode, OpenFlags, OptionalExtension};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Entries in an indexed sqlite database. Every change is written in its own
+/// transaction, so concurrent shells don't clobber each other's history.
+pub struct SqliteStore {
+    conn: Connection,
+}
+
+const SCHEMA: &str = "
+-- paths are stored as their raw bytes, they don't have to be valid unicode
+CREATE TABLE IF NOT EXISTS entries (
+    path BLOB PRIMARY KEY,
+    last_access INTEGER NOT NULL,
+    visits INTEGER NOT NULL,
+    -- machines it was visited on, one per line, empty for any
+    hosts TEXT NOT NULL DEFAULT '',
+    -- device and inode when last visited, to follow renames
+    dev INTEGER,
+    ino INTEGER,
+    -- recency order, bumped entries get the highest seq
+    seq INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS entries_seq ON entries (seq);
+CREATE TABLE IF NOT EXISTS forgotten (
+    path BLOB PRIMARY KEY,
+    at INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS transitions (
+    from_path BLOB NOT NULL,
+    to_path BLOB NOT NULL,
+    count INTEGER NOT NULL,
+    PRIMARY KEY (from_path, to_path)
+);
+CREATE TABLE IF NOT EXISTS suppressed (
+    query TEXT NOT NULL,
+    path BLOB NOT NULL,
+    PRIMARY KEY (query, path)
+);
+CREATE TABLE IF NOT EXISTS jumps (
+    at INTEGER NOT NULL,
+    path TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS snoozed (
+    path BLOB PRIMARY KEY,
+    until INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS groups (
+    name TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    path BLOB NOT NULL,
+    -- when the group was last changed, the same for all its rows
+    edited INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (name, position)
+);
+CREATE TABLE IF NOT EXISTS removed_groups (
+    name TEXT PRIMARY KEY,
+    at INTEGER NOT NULL
+);
+";
+
+fn hosts_str(hosts: &[String]) -> String {
+    hosts.join("\n")
+}
+
+fn parse_hosts(hosts: &str) -> Vec<String> {
+    hosts.lines().map(str::to_string).collect()
+}
+
+/// Columns holding paths, which databases created before they were stored
+/// as bytes have as text.
+const PATH_COLUMNS: [(&str, &str); 8] = [
+    ("entries", "path"),
+    ("forgotten", "path"),
+    ("transitions", "from_path"),
+    ("transitions", "to_path"),
+    ("suppressed", "path"),
+    ("jumps", "path"),
+    ("snoozed", "path"),
+    ("groups", "path"),
+];
+
+/// `user_version` of databases storing paths as bytes.
+const SCHEMA_VERSION: i64 = 1;
+
+fn path_bytes(path: &Path) -> Vec<u8> {
+    #[cfg(unix)]
+    return std::os::unix::ffi::OsStrExt::as_bytes(path.as_os_str()).to_vec();
+    #[cfg(not(unix))]
+    return path.to_string_lossy().into_owned().into_bytes();
+}
+
+/// A path read back from the database, whether stored as bytes or as text.
+struct DbPath(PathBuf);
+
+impl FromSql for DbPath {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        let (ValueRef::Blob(bytes) | ValueRef::Text(bytes)) = value else {
+            return Err(FromSqlError::InvalidType);
+        };
+        #[cfg(unix)]
+        let path = PathBuf::from(
+            <std::ffi::OsString as std::os::unix::ffi::OsStringExt>::from_vec(bytes.to_vec()),
+        );
+        #[cfg(not(unix))]
+        let path = PathBuf::from(String::from_utf8_lossy(bytes).into_owned());
+        Ok(Self(path))
+    }
+}
+
+/// Tell a locked or corrupt database apart from other failures to open it.
+fn opening(file_path: &Path, err: rusqlite::Error) -> eyre::Report {
+    match err.sqlite_error_code() {
+        Some(ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked) => WdError::DbLocked {
+            path: file_path.to_path_buf(),
+        }
+        .into(),
+        Some(ErrorCode::NotADatabase | ErrorCode::DatabaseCorrupt) => WdError::DbCorrupt {
+            path: file_path.to_path_buf(),
+            reason: err.to_string(),
+        }
+        .into(),
+        _ => eyre::Report::new(err).wrap_err(messages::error_opening(file_path)),
+    }
+}
+
+impl SqliteStore {
+    pub fn open(file_path: &Path) -> eyre::Result<Self> {
+        create_private(file_path).wrap_err_with(|| messages::couldnt_create(file_path))?;
+        let mut conn = Connection::open(file_path).map_err(|err| opening(file_path, err))?;
+        conn.busy_timeout(Duration::from_secs(2))?;
+        conn.execute_batch(SCHEMA)
+            .map_err(|err| opening(file_path, err))
+            .wrap_err_with(messages::sqlite_schema)?;
+        // databases created before entries were tagged with hosts or
+        // remembered their inode, or before groups were synced
+        for (table, column, definition) in [
+            ("entries", "hosts", "hosts TEXT NOT NULL DEFAULT ''"),
+            ("entries", "dev", "dev INTEGER"),
+            ("entries", "ino", "ino INTEGER"),
+            ("groups", "edited", "edited INTEGER NOT NULL DEFAULT 0"),
+        ] {
+            let exists: bool = conn.query_row(
+                "SELECT COUNT(*) > 0 FROM pragma_table_info(?1) WHERE name = ?2",
+                params![table, column],
+                |row| row.get(0),
+            )?;
+            if !exists {
+                conn.execute(&format!("ALTER TABLE {table} ADD COLUMN {definition}"), [])?;
+            }
+        }
+        let version: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        if version < SCHEMA_VERSION {
+            let tx = conn.transaction()?;
+            for (table, column) in PATH_COLUMNS {
+                tx.execute(
+                    &format!(
+                        "UPDATE {table} SET {column} = CAST({column} AS BLOB)
+                         WHERE typeof({column}) = 'text'"
+                    ),
+                    [],
+                )?;
+            }
+            tx.execute_batch(&format!("PRAGMA user_version = {SCHEMA_VERSION}"))?;
+            tx.commit()?;
+        }
+        Ok(Self { conn })
+    }
+
+    /// Open an existing database without ever writing to it.
+    pub fn open_read_only(file_path: &Path) -> eyre::Result<Self> {
+        let conn = Connection::open_with_flags(file_path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .map_err(|err| opening(file_path, err))?;
+        conn.busy_timeout(Duration::from_secs(2))?;
+        Ok(Self { conn })
+    }
+
+    fn transitions(&self) -> eyre::Result<Vec<Transition>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT from_path, to_path, count FROM transitions")?;
+        let rows = stmt.query_map([], |row| {
+            Ok(Transition {
+                from: row.get::<_, DbPath>(0)?.0,
+                to: row.get::<_, DbPath>(1)?.0,
+                count: row.get(2)?,
+            })
+        })?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    fn tombstones(&self) -> eyre::Result<Vec<Tombstone>> {
+        let mut stmt = self.conn.prepare("SELECT path, at FROM forgotten")?;
+        let rows = stmt.query_map([], |row| {
+            Ok(Tombstone {
+                path: row.get::<_, DbPath>(0)?.0,
+                at: row.get(1)?,
+            })
+        })?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    fn removed_groups(&self) -> eyre::Result<Vec<RemovedGroup>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT name, at FROM removed_groups ORDER BY name")?;
+        let rows = stmt.query_map([], |row| {
+            Ok(RemovedGroup {
+                name: row.get(0)?,
+                at: row.get(1)?,
+            })
+        })?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+}
+
+impl Store for SqliteStore {
+    fn entries(&self) -> eyre::Result<Vec<Entry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT path, last_access, visits, hosts, dev, ino FROM entries ORDER BY seq DESC",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            let (dev, ino): (Option<u64>, Option<u64>) = (row.get(4)?, row.get(5)?);
+            Ok(Entry {
+                path: row.get::<_, DbPath>(0)?.0,
+                last_access: row.get(1)?,
+                visits: row.get(2)?,
+                hosts: parse_hosts(&row.get::<_, String>(3)?),
+                id: dev.zip(ino).map(|(dev, ino)| FileId { dev, ino }),
+            })
+        })?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    fn bump(&mut self, path: PathBuf, host: Option<&str>) -> eyre::Result<()> {
+        let id = FileId::of(&path);
+        let path = path_bytes(&path);
+        let tx = self.conn.transaction()?;
+        let mut hosts: Vec<String> = tx
+            .query_row(
+                "SELECT hosts FROM entries WHERE path = ?1",
+                params![path],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()?
+            .map(|hosts| parse_hosts(&hosts))
+            .unwrap_or_default();
+        if let Some(host) = host {
+            if !hosts.iter().any(|h| h == host) {
+                hosts.push(host.to_string());
+            }
+        }
+        tx.execute(
+            "INSERT INTO entries (path, last_access, visits, hosts, dev, ino, seq)
+             VALUES (?1, ?2, 1, ?3, ?4, ?5, (SELECT COALESCE(MAX(seq), 0) + 1 FROM entries))
+             ON CONFLICT (path) DO UPDATE SET
+                 last_access = excluded.last_access,
+                 visits = visits + 1,
+                 hosts = excluded.hosts,
+                 dev = COALESCE(excluded.dev, dev),
+                 ino = COALESCE(excluded.ino, ino),
+                 seq = excluded.seq",
+            params![
+                path,
+                now(),
+                hosts_str(&hosts),
+                id.map(|id| id.dev),
+                id.map(|id| id.ino)
+            ],
+        )?;
+        tx.execute("DELETE FROM forgotten WHERE path = ?1", params![path])?;
+        tx.execute(
+            "INSERT INTO jumps (at, path) VALUES (?1, ?2)",
+            params![now(), path],
+        )?;
+        tx.execute(
+            "DELETE FROM jumps WHERE rowid NOT IN
+                 (SELECT rowid FROM jumps ORDER BY at DESC, rowid DESC LIMIT ?1)",
+            params![JUMP_LOG_SIZE],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn forget(&mut self, path: PathBuf) -> eyre::Result<()> {
+        let path = path_bytes(&path);
+        let tx = self.conn.transaction()?;
+        tx.execute("DELETE FROM entries WHERE path = ?1", params![path])?;
+        tx.execute(
+            "INSERT OR REPLACE INTO forgotten (path, at) VALUES (?1, ?2)",
+            params![path, now()],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn suppressions(&self) -> eyre::Result<Vec<Suppression>> {
+        let mut stmt = self.conn.prepare("SELECT query, path FROM suppressed")?;
+        let rows = stmt.query_map([], |row| {
+            Ok(Suppression {
+                query: row.get(0)?,
+                path: row.get::<_, DbPath>(1)?.0,
+            })
+        })?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    fn suppress(&mut self, suppression: Suppression) -> eyre::Result<()> {
+        self.conn.execute(
+            "INSERT OR IGNORE INTO suppressed (query, path) VALUES (?1, ?2)",
+            params![suppression.query, path_bytes(&suppression.path)],
+        )?;
+        Ok(())
+    }
+
+    fn snoozed(&self) -> eyre::Result<Vec<Snooze>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT path, until FROM snoozed WHERE until > ?1")?;
+        let rows = stmt.query_map(params![now()], |row| {
+            Ok(Snooze {
+                path: row.get::<_, DbPath>(0)?.0,
+                until: row.get(1)?,
+            })
+        })?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    fn snooze(&mut self, path: PathBuf, until: u64) -> eyre::Result<()> {
+        let tx = self.conn.transaction()?;
+        tx.execute("DELETE FROM snoozed WHERE until <= ?1", params![now()])?;
+        tx.execute(
+            "INSERT OR REPLACE INTO snoozed (path, until) VALUES (?1, ?2)",
+            params![path_bytes(&path), until],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn jumps(&self) -> eyre::Result<Vec<Jump>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT at, path FROM jumps ORDER BY at, rowid")?;
+        let rows = stmt.query_map([], |row| {
+            Ok(Jump {
+                at: row.get(0)?,
+                path: row.get::<_, DbPath>(1)?.0,
+            })
+        })?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    fn transitions_from(&self, from: &Path) -> eyre::Result<Vec<Transition>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT to_path, count FROM transitions WHERE from_path = ?1")?;
+        let rows = stmt.query_map(params![path_bytes(from)], |row| {
+            Ok(Transition {
+                from: from.to_path_buf(),
+                to: row.get::<_, DbPath>(0)?.0,
+                count: row.get(1)?,
+            })
+        })?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    fn record_transition(&mut self, from: PathBuf, to: PathBuf) -> eyre::Result<()> {
+        self.conn.execute(
+            "INSERT INTO transitions (from_path, to_path, count) VALUES (?1, ?2, 1)
+             ON CONFLICT (from_path, to_path) DO UPDATE SET count = count + 1",
+            params![path_bytes(&from), path_bytes(&to)],
+        )?;
+        Ok(())
+    }
+
+    fn groups(&self) -> eyre::Result<Vec<Group>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT name, path, edited FROM groups ORDER BY name, position")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, DbPath>(1)?.0,
+                row.get::<_, u64>(2)?,
+            ))
+        })?;
+        let mut groups: Vec<Group> = vec![];
+        for row in rows {
+            let (name, path, edited) = row?;
+            match groups.last_mut() {
+                Some(group) if group.name == name => group.paths.push(path),
+                _ => groups.push(Group {
+                    name,
+                    paths: vec![path],
+                    edited,
+                }),
+            }
+        }
+        Ok(groups)
+    }
+
+    fn set_group(&mut self, group: Group) -> eyre::Result<()> {
+        let at = now();
+        let tx = self.conn.transaction()?;
+        tx.execute("DELETE FROM groups WHERE name = ?1", params![group.name])?;
+        tx.execute(
+            "DELETE FROM removed_groups WHERE name = ?1",
+            params![group.name],
+        )?;
+        if group.paths.is_empty() {
+            tx.execute(
+                "INSERT INTO removed_groups (name, at) VALUES (?1, ?2)",
+                params![group.name, at],
+            )?;
+        }
+        {
+            let mut insert = tx.prepare(
+                "INSERT INTO groups (name, position, path, edited) VALUES (?1, ?2, ?3, ?4)",
+            )?;
+            for (i, path) in group.paths.iter().enumerate() {
+                insert.execute(params![group.name, i, path_bytes(path), at])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn export(&self) -> eyre::Result<DBContent> {
+        Ok(DBContent {
+            paths: self.entries()?,
+            forgotten: self.tombstones()?,
+            suppressed: self.suppressions()?,
+            transitions: self.transitions()?,
+            snoozed: self.snoozed()?,
+            jumps: self.jumps()?,
+            groups: self.groups()?,
+            removed_groups: self.removed_groups()?,
+        })
+    }
+
+    fn import(&mut self, content: DBContent) -> eyre::Result<()> {
+        let tx = self.conn.transaction()?;
+        tx.execute("DELETE FROM entries", [])?;
+        tx.execute("DELETE FROM forgotten", [])?;
+        tx.execute("DELETE FROM suppressed", [])?;
+        tx.execute("DELETE FROM transitions", [])?;
+        tx.execute("DELETE FROM snoozed", [])?;
+        tx.execute("DELETE FROM jumps", [])?;
+        tx.execute("DELETE FROM groups", [])?;
+        tx.execute("DELETE FROM removed_groups", [])?;
+        {
+            // the same path twice, as paths turned the same by a migration
+            // can be, is merged rather than failing the whole import
+            let mut insert = tx.prepare(
+                "INSERT INTO entries (path, last_access, visits, hosts, dev, ino, seq)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+                 ON CONFLICT (path) DO UPDATE SET
+                     last_access = MAX(last_access, excluded.last_access),
+                     visits = visits + excluded.visits,
+                     seq = MAX(seq, excluded.seq)",
+            )?;
+            let len = content.paths.len();
+            for (i, e) in content.paths.iter().enumerate() {
+                insert.execute(params![
+                    path_bytes(&e.path),
+                    e.last_access,
+                    e.visits,
+                    hosts_str(&e.hosts),
+                    e.id.map(|id| id.dev),
+                    e.id.map(|id| id.ino),
+                    len - i
+                ])?;
+            }
+            let mut insert = tx.prepare(
+                "INSERT INTO forgotten (path, at) VALUES (?1, ?2)
+                 ON CONFLICT (path) DO UPDATE SET at = MAX(at, excluded.at)",
+            )?;
+            for t in &content.forgotten {
+                insert.execute(params![path_bytes(&t.path), t.at])?;
+            }
+            let mut insert =
+                tx.prepare("INSERT OR IGNORE INTO suppressed (query, path) VALUES (?1, ?2)")?;
+            for s in &content.suppressed {
+                insert.execute(params![s.query, path_bytes(&s.path)])?;
+            }
+            let mut insert = tx.prepare(
+                "INSERT INTO transitions (from_path, to_path, count) VALUES (?1, ?2, ?3)
+                 ON CONFLICT (from_path, to_path) DO UPDATE SET count = count + excluded.count",
+            )?;
+            for t in &content.transitions {
+                insert.execute(params![path_bytes(&t.from), path_bytes(&t.to), t.count])?;
+            }
+            let mut insert = tx.prepare(
+                "INSERT INTO snoozed (path, until) VALUES (?1, ?2)
+                 ON CONFLICT (path) DO UPDATE SET until = MAX(until, excluded.until)",
+            )?;
+            for s in &content.snoozed {
+                insert.execute(params![path_bytes(&s.path), s.until])?;
+            }
+            let mut insert = tx.prepare("INSERT INTO jumps (at, path) VALUES (?1, ?2)")?;
+            for j in &content.jumps {
+                insert.execute(params![j.at, path_bytes(&j.path)])?;
+            }
+            let mut insert = tx.prepare(
+                "INSERT OR REPLACE INTO groups (name, position, path, edited)
+                 VALUES (?1, ?2, ?3, ?4)",
+            )?;
+            for g in &content.groups {
+                for (i, path) in g.paths.iter().enumerate() {
+                    insert.execute(params![g.name, i, path_bytes(path), g.edited])?;
+                }
+            }
+            let mut insert = tx.prepare(
+                "INSERT INTO removed_groups (name, at) VALUES (?1, ?2)
+                 ON CONFLICT (name) DO UPDATE SET at = MAX(at, excluded.at)",
+            )?;
+            for r in &content.removed_groups {
+                insert.execute(params![r.name, r.at])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> eyre::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    fn temp_db(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("wd-test-{name}-{}", std::process::id()));
+        std::fs::remove_file(&path).ok();
+        path
+    }
+
+    #[test]
+    fn paths_keep_their_bytes() {
+        let file = temp_db("sqlite-bytes");
+        let mut db = SqliteStore::open(&file).unwrap();
+        let latin1 = PathBuf::from(OsStr::from_bytes(b"/data/caf\xe9"));
+        let replaced = PathBuf::from("/data/caf\u{fffd}");
+        db.bump(latin1.clone(), None).unwrap();
+        db.bump(replaced.clone(), None).unwrap();
+        let paths: Vec<_> = db.entries().unwrap().into_iter().map(|e| e.path).collect();
+        std::fs::remove_file(&file).unwrap();
+        assert_eq!(paths, [replaced, latin1]);
+    }
+
+    #[test]
+    fn text_paths_of_older_databases_are_converted() {
+        let file = temp_db("sqlite-text");
+        {
+            let conn = Connection::open(&file).unwrap();
+            conn.execute_batch(
+                "CREATE TABLE entries (path TEXT PRIMARY KEY, last_access INTEGER NOT NULL,
+                     visits INTEGER NOT NULL, seq INTEGER NOT NULL);
+                 INSERT INTO entries VALUES ('/home/user/work', 1, 3, 1);",
+            )
+            .unwrap();
+        }
+        let mut db = SqliteStore::open(&file).unwrap();
+        db.bump("/home/user/work".into(), None).unwrap();
+        let entries = db.entries().unwrap();
+        std::fs::remove_file(&file).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].visits, 4);
+    }
+
+    #[test]
+    fn importing_the_same_path_twice_merges_it() {
+        let file = temp_db("sqlite-import");
+        let mut db = SqliteStore::open(&file).unwrap();
+        let entry = |last_access, visits| Entry {
+            path: "/home/user/work".into(),
+            last_access,
+            visits,
+            hosts: vec![],
+            id: None,
+        };
+        let mut content = DBContent::new();
+        content.paths = vec![entry(10, 2), entry(20, 3)];
+        db.import(content).unwrap();
+        let entries = db.entries().unwrap();
+        std::fs::remove_file(&file).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!((entries[0].last_access, entries[0].visits), (20, 5));
+    }
+}
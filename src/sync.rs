@@ -0,0 +1,336 @@
+use crate::messages;
+use crate::private;
+use crate::store::{
+    load_json, now, DBContent, Entry, Group, RemovedGroup, Snooze, Store, Tombstone, Transition,
+    JUMP_LOG_SIZE,
+};
+use eyre::{bail, Context};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::Command;
+
+pub enum Remote {
+    Local(PathBuf),
+    Ssh(String),
+}
+
+impl Remote {
+    /// same rule as scp: a colon before any slash means `host:path`
+    pub fn parse(remote: &str) -> Self {
+        match remote.find(':') {
+            Some(i) if !remote[..i].contains('/') => Self::Ssh(remote.to_string()),
+            _ => Self::Local(remote.into()),
+        }
+    }
+
+    fn fetch(&self) -> eyre::Result<DBContent> {
+        match self {
+            Self::Local(path) => load_json(path),
+            Self::Ssh(target) => {
+                let dir = tmp_dir()?;
+                let tmp = dir.join("wddb");
+                let fetched = scp(target, &tmp.to_string_lossy());
+                let content = fetched.map(|()| DBContent::load(&tmp));
+                std::fs::remove_dir_all(&dir).ok();
+                Ok(content?
+                    .wrap_err_with(messages::loading_remote_db)?
+                    .unwrap_or_else(DBContent::new))
+            }
+        }
+    }
+
+    fn push(&self, content: &DBContent) -> eyre::Result<()> {
+        match self {
+            Self::Local(path) => content
+                .write_to(path)
+                .wrap_err_with(|| messages::error_writing(path)),
+            Self::Ssh(target) => {
+                let dir = tmp_dir()?;
+                let tmp = dir.join("wddb");
+                let res = content
+                    .write_to(&tmp)
+                    .map_err(eyre::Report::from)
+                    .and_then(|()| scp(&tmp.to_string_lossy(), target));
+                std::fs::remove_dir_all(&dir).ok();
+                res
+            }
+        }
+    }
+}
+
+/// A fresh directory only the user can enter, to hold the db on its way to
+/// or from scp. Created rather than reused, so another user can't have put a
+/// symlink there.
+fn tmp_dir() -> eyre::Result<PathBuf> {
+    let dir = std::env::temp_dir().join(format!(
+        "wd-sync-{}-{}",
+        std::process::id(),
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.subsec_nanos())
+    ));
+    let mut builder = std::fs::DirBuilder::new();
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    builder
+        .create(&dir)
+        .wrap_err_with(|| messages::couldnt_create(&dir))?;
+    Ok(dir)
+}
+
+fn scp(from: &str, to: &str) -> eyre::Result<()> {
+    let status = Command::new("scp")
+        .args(["-q", from, to])
+        .status()
+        .wrap_err_with(|| messages::couldnt_run("scp"))?;
+    if !status.success() {
+        bail!(messages::command_failed(
+            &format!("scp {from} {to}"),
+            status
+        ));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Default)]
+pub struct SyncReport {
+    pub added: usize,
+    pub updated: usize,
+    pub removed: usize,
+}
+
+impl DBContent {
+    /// Merge `other` into `self`. Entries are merged per path keeping the
+    /// most recent access and highest visit count, and a forget wins over any
+    /// access that happened before it, so merging is commutative and
+    /// idempotent and both sides converge to the same content.
+    pub fn merge(&mut self, other: Self) -> SyncReport {
+        let mut report = SyncReport::default();
+
+        let mut forgotten: HashMap<PathBuf, u64> = HashMap::new();
+        for t in self.forgotten.drain(..).chain(other.forgotten) {
+            let at = forgotten.entry(t.path).or_insert(0);
+            *at = (*at).max(t.at);
+        }
+
+        let local_len = self.paths.len();
+        let mut index: HashMap<PathBuf, usize> = self
+            .paths
+            .iter()
+            .enumerate()
+            .map(|(i, e)| (e.path.clone(), i))
+            .collect();
+        for entry in other.paths {
+            match index.get(&entry.path) {
+                Some(&i) => {
+                    let local = &mut self.paths[i];
+                    if entry.last_access > local.last_access || entry.visits > local.visits {
+                        report.updated += 1;
+                    }
+                    local.last_access = local.last_access.max(entry.last_access);
+                    local.visits = local.visits.max(entry.visits);
+                    for host in entry.hosts {
+                        if !local.hosts.contains(&host) {
+                            local.hosts.push(host);
+                        }
+                    }
+                    local.hosts.sort();
+                }
+                None => {
+                    index.insert(entry.path.clone(), self.paths.len());
+                    // the other side's device and inode mean nothing here
+                    self.paths.push(Entry { id: None, ..entry });
+                }
+            }
+        }
+
+        let mut i = 0;
+        self.paths.retain(|e| {
+            let is_local = i < local_len;
+            i += 1;
+            let alive = forgotten.get(&e.path).is_none_or(|&at| e.last_access > at);
+            match (alive, is_local) {
+                (true, false) => report.added += 1,
+                (false, true) => report.removed += 1,
+                _ => {}
+            }
+            alive
+        });
+
+        // stable, so entries without timestamps keep their local order
+        self.paths.sort_by_key(|e| std::cmp::Reverse(e.last_access));
+        self.forgotten = forgotten
+            .into_iter()
+            .map(|(path, at)| Tombstone { path, at })
+            .collect();
+        self.forgotten.sort_by(|a, b| a.path.cmp(&b.path));
+
+        // suppressions only ever get added, keep them all
+        self.suppressed.extend(other.suppressed);
+        self.suppressed.sort();
+        self.suppressed.dedup();
+
+        // counts only grow, so the highest one is the most up to date
+        let mut transitions: HashMap<(PathBuf, PathBuf), u64> = HashMap::new();
+        for t in self.transitions.drain(..).chain(other.transitions) {
+            let count = transitions.entry((t.from, t.to)).or_insert(0);
+            *count = (*count).max(t.count);
+        }
+        self.transitions = transitions
+            .into_iter()
+            .map(|((from, to), count)| Transition { from, to, count })
+            .collect();
+        self.transitions
+            .sort_by(|a, b| (&a.from, &a.to).cmp(&(&b.from, &b.to)));
+
+        // the longest snooze wins, expired ones are dropped
+        let now = now();
+        let mut snoozed: HashMap<PathBuf, u64> = HashMap::new();
+        for s in self.snoozed.drain(..).chain(other.snoozed) {
+            let until = snoozed.entry(s.path).or_insert(0);
+            *until = (*until).max(s.until);
+        }
+        self.snoozed = snoozed
+            .into_iter()
+            .filter(|&(_, until)| until > now)
+            .map(|(path, until)| Snooze { path, until })
+            .collect();
+        self.snoozed.sort_by(|a, b| a.path.cmp(&b.path));
+
+        // both logs interleaved by time, keeping the most recent jumps
+        self.jumps.extend(other.jumps);
+        self.jumps.sort();
+        self.jumps.dedup();
+        if self.jumps.len() > JUMP_LOG_SIZE {
+            self.jumps.drain(..self.jumps.len() - JUMP_LOG_SIZE);
+        }
+
+        // like entries: the most recent edit of a group wins, and a removal
+        // wins over any edit made before it
+        let mut removed: HashMap<String, u64> = HashMap::new();
+        for r in self.removed_groups.drain(..).chain(other.removed_groups) {
+            let at = removed.entry(r.name).or_insert(0);
+            *at = (*at).max(r.at);
+        }
+        let mut groups: HashMap<String, Group> = HashMap::new();
+        for group in self.groups.drain(..).chain(other.groups) {
+            match groups.get(&group.name) {
+                Some(kept) if kept.edited >= group.edited => {}
+                _ => {
+                    groups.insert(group.name.clone(), group);
+                }
+            }
+        }
+        self.groups = groups
+            .into_values()
+            .filter(|g| removed.get(&g.name).is_none_or(|&at| g.edited > at))
+            .collect();
+        self.groups.sort_by(|a, b| a.name.cmp(&b.name));
+        self.removed_groups = removed
+            .into_iter()
+            .map(|(name, at)| RemovedGroup { name, at })
+            .collect();
+        self.removed_groups.sort_by(|a, b| a.name.cmp(&b.name));
+        report
+    }
+}
+
+/// Merge `remote` into `db`, and back into `remote` with `push`. Directories
+/// under `private` paths are left out on both sides, whichever had them.
+pub fn sync(
+    db: &mut dyn Store,
+    remote: &Remote,
+    push: bool,
+    private: &[PathBuf],
+) -> eyre::Result<SyncReport> {
+    let mut content = db.export()?;
+    let mut report = content.merge(remote.fetch()?);
+    let scrubbed = private::scrub(&mut content, private);
+    report.added = report.added.saturating_sub(scrubbed);
+    if push {
+        remote.push(&content)?;
+    }
+    db.import(content)?;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::MemoryStore;
+
+    fn group(name: &str, paths: &[&str]) -> Group {
+        Group {
+            name: name.to_string(),
+            paths: paths.iter().map(PathBuf::from).collect(),
+            edited: 0,
+        }
+    }
+
+    #[test]
+    fn removed_groups_stay_removed() {
+        let mut local = DBContent::new();
+        local.set_group(group("acme", &["/work/front", "/work/back"]));
+        local.set_group(group("home", &["/home/a", "/home/b"]));
+        let remote = local.clone();
+        local.set_group(group("acme", &[]));
+
+        local.merge(remote.clone());
+        let names = |content: &DBContent| {
+            content
+                .groups
+                .iter()
+                .map(|g| g.name.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(&local), ["home"]);
+
+        // and the other side learns about the removal
+        let mut other = remote;
+        other.merge(local);
+        assert_eq!(names(&other), ["home"]);
+    }
+
+    #[test]
+    fn newer_group_edits_win() {
+        let mut local = DBContent::new();
+        local.groups.push(Group {
+            edited: 10,
+            ..group("acme", &["/work/front", "/work/back"])
+        });
+        let mut remote = DBContent::new();
+        remote.groups.push(Group {
+            edited: 20,
+            ..group("acme", &["/work/front", "/work/back", "/work/infra"])
+        });
+        local.merge(remote);
+        assert_eq!(local.groups[0].paths.len(), 3);
+    }
+
+    #[test]
+    fn private_paths_from_the_remote_are_left_out() {
+        let file = std::env::temp_dir().join(format!("wd-test-sync-{}", std::process::id()));
+        let mut remote = DBContent::new();
+        remote.bump(PathBuf::from("/home/user/secret/diary"), None);
+        remote.bump(PathBuf::from("/home/user/work"), None);
+        remote.write_to(&file).unwrap();
+
+        let mut db = MemoryStore::default();
+        let private = [PathBuf::from("/home/user/secret")];
+        let report = sync(&mut db, &Remote::Local(file.clone()), true, &private).unwrap();
+        let pushed = load_json(&file).unwrap();
+        std::fs::remove_file(&file).unwrap();
+
+        let paths = |entries: &[Entry]| entries.iter().map(|e| e.path.clone()).collect::<Vec<_>>();
+        assert_eq!(report.added, 1);
+        assert_eq!(
+            paths(&db.entries().unwrap()),
+            [PathBuf::from("/home/user/work")]
+        );
+        assert_eq!(paths(&pushed.paths), [PathBuf::from("/home/user/work")]);
+        assert!(pushed
+            .jumps
+            .iter()
+            .all(|j| !j.path.starts_with(&private[0])));
+    }
+}
@@ -0,0 +1,100 @@
+//! Time-of-day and weekday habits: directories mostly visited at the same
+//! part of the day, on the same kind of day as now, get a small boost. The
+//! visits come from the jump log, so only the last `JUMP_LOG_SIZE` jumps
+//! count.
+
+use crate::config::TemporalConfig;
+use crate::store::Jump;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+/// Fewest logged visits a directory needs before its habits are trusted.
+pub const MIN_VISITS: usize = 3;
+
+/// Part of the week a visit falls in: weekday or weekend, and one of four
+/// six-hour parts of the day starting at midnight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Slot {
+    pub weekend: bool,
+    pub part: u8,
+}
+
+impl Slot {
+    /// Slot of `timestamp`, `offset` seconds ahead of UTC.
+    pub fn of(timestamp: u64, offset: i64) -> Self {
+        let local = (timestamp as i64 + offset).max(0) as u64;
+        let days = local / 86400;
+        // the epoch was a thursday, 0 is monday
+        let weekday = (days + 3) % 7;
+        Self {
+            weekend: weekday >= 5,
+            part: (local % 86400 / (6 * 3600)) as u8,
+        }
+    }
+}
+
+/// `+02:00`, `-0530` or `+1` as seconds ahead of UTC.
+pub fn parse_offset(raw: &str) -> Option<i64> {
+    let raw = raw.trim();
+    let (sign, rest) = match raw.as_bytes().first()? {
+        b'+' => (1, &raw[1..]),
+        b'-' => (-1, &raw[1..]),
+        _ => (1, raw),
+    };
+    let digits: String = rest.chars().filter(|&c| c != ':').collect();
+    if digits.is_empty() || digits.len() > 4 || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let (hours, minutes) = if digits.len() > 2 {
+        digits.split_at(digits.len() - 2)
+    } else {
+        (digits.as_str(), "0")
+    };
+    let (hours, minutes): (i64, i64) = (hours.parse().ok()?, minutes.parse().ok()?);
+    (hours <= 14 && minutes < 60).then(|| sign * (hours * 3600 + minutes * 60))
+}
+
+/// Seconds ahead of UTC: `utc_offset` from the config, or the C library's
+/// offset for the local timezone right now, or UTC if neither works.
+pub fn local_offset(config: &TemporalConfig) -> i64 {
+    if let Some(offset) = config.utc_offset.as_deref().and_then(parse_offset) {
+        return offset;
+    }
+    static SYSTEM: OnceLock<i64> = OnceLock::new();
+    *SYSTEM.get_or_init(|| system_offset().unwrap_or(0))
+}
+
+#[cfg(unix)]
+fn system_offset() -> Option<i64> {
+    let now = crate::store::now() as libc::time_t;
+    // SAFETY: `tm` is plain data, fully written by `localtime_r` when it
+    // doesn't return null, and both pointers are valid for the call.
+    unsafe {
+        let mut tm = std::mem::zeroed::<libc::tm>();
+        (!libc::localtime_r(&now, &mut tm).is_null()).then_some(tm.tm_gmtoff as i64)
+    }
+}
+
+#[cfg(not(unix))]
+const fn system_offset() -> Option<i64> {
+    None
+}
+
+/// For each directory visited at least `MIN_VISITS` times in `jumps`, the
+/// share of its visits made in `slot`.
+pub fn affinity(jumps: &[Jump], slot: Slot, offset: i64) -> HashMap<PathBuf, f64> {
+    let mut counts: HashMap<&PathBuf, (usize, usize)> = HashMap::new();
+    for jump in jumps {
+        let (total, matching) = counts.entry(&jump.path).or_default();
+        *total += 1;
+        if Slot::of(jump.at, offset) == slot {
+            *matching += 1;
+        }
+    }
+    counts
+        .into_iter()
+        .filter(|&(_, (total, matching))| total >= MIN_VISITS && matching > 0)
+        .map(|(path, (total, matching))| (path.clone(), matching as f64 / total as f64))
+        .collect()
+}
@@ -0,0 +1,67 @@
+//! Durations as people type and read them: `90s`, `45m`, `12h`, `3d`, `2w`,
+//! or combinations like `1d12h`, and timestamps.
+
+use crate::messages;
+
+const UNITS: &[(char, u64)] = &[
+    ('w', 7 * 24 * 3600),
+    ('d', 24 * 3600),
+    ('h', 3600),
+    ('m', 60),
+    ('s', 1),
+];
+
+/// Parse a duration into seconds, for clap's `value_parser`.
+pub fn parse_duration(raw: &str) -> Result<u64, String> {
+    let mut total: u64 = 0;
+    let mut number = String::new();
+    for c in raw.trim().chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let (_, secs) = UNITS
+            .iter()
+            .find(|(unit, _)| *unit == c)
+            .ok_or_else(|| messages::unknown_unit(c))?;
+        let n: u64 = number.parse().map_err(|_| messages::missing_number(c))?;
+        total = total.saturating_add(n.saturating_mul(*secs));
+        number.clear();
+    }
+    if !number.is_empty() {
+        return Err(messages::missing_unit(&number));
+    }
+    if total == 0 {
+        return Err(messages::duration_not_positive());
+    }
+    Ok(total)
+}
+
+/// Roughly how long `secs` is, in its largest unit.
+pub fn format_duration(secs: u64) -> String {
+    UNITS
+        .iter()
+        .find(|(_, unit)| secs >= *unit)
+        .map_or_else(|| "0s".into(), |(c, unit)| format!("{}{c}", secs / unit))
+}
+
+/// `timestamp` as `YYYY-MM-DD HH:MM` in UTC.
+pub fn format_timestamp(timestamp: u64) -> String {
+    let (days, secs) = (timestamp / 86400, timestamp % 86400);
+    // civil date from days since the epoch, see
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}",
+        secs / 3600,
+        secs % 3600 / 60
+    )
+}
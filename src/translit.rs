@@ -0,0 +1,154 @@
+//! Optional stages rewriting paths and queries before they're compared, so
+//! names outside plain ASCII still get sensible fuzzy matches. Each enabled
+//! stage is applied to both sides and the best of the original and rewritten
+//! scores is kept.
+
+use pinyin::ToPinyin;
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Stage {
+    /// drop diacritics: `café` matches `cafe`
+    Fold,
+    /// romanize Cyrillic: `проекты` matches `proekty`
+    Cyrillic,
+    /// read Latin letters as typed on a Russian keyboard: `ghjtrn` matches
+    /// `проект`
+    KeyboardRu,
+    /// initials of Chinese characters: `项目` matches `xm`
+    PinyinInitials,
+}
+
+const FOLD: &[(&str, char)] = &[
+    ("àáâãäåāăą", 'a'),
+    ("çćĉċč", 'c'),
+    ("ďđ", 'd'),
+    ("èéêëēĕėęě", 'e'),
+    ("ĝğġģ", 'g'),
+    ("ĥħ", 'h'),
+    ("ìíîïĩīĭįı", 'i'),
+    ("ĵ", 'j'),
+    ("ķ", 'k'),
+    ("ĺļľŀł", 'l'),
+    ("ñńņňŉ", 'n'),
+    ("òóôõöøōŏő", 'o'),
+    ("ŕŗř", 'r'),
+    ("śŝşšș", 's'),
+    ("ţťŧț", 't'),
+    ("ùúûüũūŭůűų", 'u'),
+    ("ŵ", 'w'),
+    ("ýÿŷ", 'y'),
+    ("źżž", 'z'),
+];
+
+const CYRILLIC: &[(char, &str)] = &[
+    ('а', "a"),
+    ('б', "b"),
+    ('в', "v"),
+    ('г', "g"),
+    ('ґ', "g"),
+    ('д', "d"),
+    ('е', "e"),
+    ('ё', "e"),
+    ('є', "ye"),
+    ('ж', "zh"),
+    ('з', "z"),
+    ('и', "i"),
+    ('і', "i"),
+    ('ї', "yi"),
+    ('й', "y"),
+    ('к', "k"),
+    ('л', "l"),
+    ('м', "m"),
+    ('н', "n"),
+    ('о', "o"),
+    ('п', "p"),
+    ('р', "r"),
+    ('с', "s"),
+    ('т', "t"),
+    ('у', "u"),
+    ('ф', "f"),
+    ('х', "kh"),
+    ('ц', "ts"),
+    ('ч', "ch"),
+    ('ш', "sh"),
+    ('щ', "shch"),
+    ('ъ', ""),
+    ('ы', "y"),
+    ('ь', ""),
+    ('э', "e"),
+    ('ю', "yu"),
+    ('я', "ya"),
+];
+
+const QWERTY: &str = "`qwertyuiop[]asdfghjkl;'zxcvbnm,.";
+const JCUKEN: &str = "ёйцукенгшщзхъфывапролджэячсмитьбю";
+
+fn fold(c: char) -> char {
+    let lower = c.to_lowercase().next().unwrap_or(c);
+    FOLD.iter()
+        .find(|(from, _)| from.contains(lower))
+        .map_or(c, |&(_, to)| {
+            if c.is_uppercase() {
+                to.to_ascii_uppercase()
+            } else {
+                to
+            }
+        })
+}
+
+fn cyrillic(c: char, out: &mut String) {
+    let lower = c.to_lowercase().next().unwrap_or(c);
+    match CYRILLIC.iter().find(|(from, _)| *from == lower) {
+        Some((_, to)) if c.is_uppercase() => {
+            let mut chars = to.chars();
+            out.extend(chars.next().map(|c| c.to_ascii_uppercase()));
+            out.extend(chars);
+        }
+        Some((_, to)) => out.push_str(to),
+        None => out.push(c),
+    }
+}
+
+fn keyboard_ru(c: char) -> char {
+    let lower = c.to_ascii_lowercase();
+    QWERTY
+        .chars()
+        .position(|q| q == lower)
+        .and_then(|i| JCUKEN.chars().nth(i))
+        .map_or(c, |r| {
+            if c.is_ascii_uppercase() {
+                r.to_uppercase().next().unwrap_or(r)
+            } else {
+                r
+            }
+        })
+}
+
+impl Stage {
+    pub fn apply(self, s: &str) -> String {
+        let mut out = String::with_capacity(s.len());
+        for c in s.chars() {
+            match self {
+                Self::Fold => out.push(fold(c)),
+                Self::Cyrillic => cyrillic(c, &mut out),
+                Self::KeyboardRu => out.push(keyboard_ru(c)),
+                Self::PinyinInitials => match c.to_pinyin() {
+                    Some(pinyin) => out.push_str(pinyin.first_letter()),
+                    None => out.push(c),
+                },
+            }
+        }
+        out
+    }
+}
+
+/// Key to order names by once every stage is applied, so `Émile` sorts next
+/// to `emile` rather than after `z`.
+pub fn collation_key(s: &str, stages: &[Stage]) -> String {
+    stages
+        .iter()
+        .fold(s.to_string(), |s, stage| stage.apply(&s))
+        .to_lowercase()
+}
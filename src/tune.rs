@@ -0,0 +1,284 @@
+//! Learning a better default confidence from how matches are used.
+//!
+//! A fuzzy match that was jumped to counts as accepted, unless the user
+//! forgets that directory right after, then it counts as rejected. A query
+//! that matched nothing remembers its best candidate, and if the user then
+//! goes to that directory by its path the candidate's score counts as accepted
+//! too, since the threshold was in the way.
+//!
+//! The same history catches corrections: jumping somewhere for a query and
+//! leaving right away for another directory matching that query means the
+//! first one was the wrong answer. Once that happened often enough, the wrong
+//! answer gets suppressed for that query.
+
+use crate::messages;
+use crate::store::{self, now};
+use eyre::Context;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+const BUCKETS: usize = 20;
+const BUCKET_WIDTH: f64 = 1. / BUCKETS as f64;
+/// below this many samples, suggestions are mostly noise
+const MIN_SAMPLES: u32 = 20;
+/// seconds after a jump during which leaving counts as a correction
+const CORRECTION_WINDOW: u64 = 60;
+/// corrections after which a match is suppressed for its query
+pub const SUPPRESS_AFTER: u32 = 2;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct Sample {
+    path: PathBuf,
+    score: f64,
+    #[serde(default)]
+    query: String,
+    #[serde(default)]
+    at: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct Correction {
+    query: String,
+    path: PathBuf,
+    count: u32,
+}
+
+/// Histograms of accepted and rejected match scores.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Stats {
+    #[serde(default)]
+    accepted: Vec<u32>,
+    #[serde(default)]
+    rejected: Vec<u32>,
+    last_jump: Option<Sample>,
+    last_miss: Option<Sample>,
+    #[serde(default)]
+    corrections: Vec<Correction>,
+
+    /// `None` when nothing should be written
+    #[serde(skip)]
+    file_path: Option<PathBuf>,
+}
+
+/// Empty histograms, sized so recording a jump can't index past them.
+impl Default for Stats {
+    fn default() -> Self {
+        Self {
+            accepted: vec![0; BUCKETS],
+            rejected: vec![0; BUCKETS],
+            last_jump: None,
+            last_miss: None,
+            corrections: Vec::new(),
+            file_path: None,
+        }
+    }
+}
+
+fn bucket(score: f64) -> usize {
+    ((score / BUCKET_WIDTH) as usize).min(BUCKETS - 1)
+}
+
+impl Stats {
+    pub fn open(file_path: PathBuf) -> eyre::Result<Self> {
+        let mut stats: Self = match std::fs::read(&file_path) {
+            Ok(raw) => serde_json::from_slice(&raw)
+                .wrap_err_with(|| messages::error_loading(&file_path))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Self::default(),
+            Err(e) => return Err(e).wrap_err_with(|| messages::error_loading(&file_path)),
+        };
+        stats.accepted.resize(BUCKETS, 0);
+        stats.rejected.resize(BUCKETS, 0);
+        stats.file_path = Some(file_path);
+        Ok(stats)
+    }
+
+    /// Stats loaded from `file_path` if it exists, never written back.
+    pub fn open_read_only(file_path: PathBuf) -> eyre::Result<Self> {
+        let mut stats = Self::open(file_path)?;
+        stats.file_path = None;
+        Ok(stats)
+    }
+
+    pub fn write(&self) -> eyre::Result<()> {
+        let Some(file_path) = &self.file_path else {
+            return Ok(());
+        };
+        store::write_private(file_path, &serde_json::to_vec(self)?)
+            .wrap_err_with(|| messages::error_writing(file_path))
+    }
+
+    /// A fuzzy match was jumped to.
+    pub fn jumped(&mut self, query: &str, path: &Path, score: f64) {
+        self.accepted[bucket(score)] += 1;
+        self.last_jump = Some(Sample {
+            path: path.to_path_buf(),
+            score,
+            query: query.to_string(),
+            at: now(),
+        });
+        self.last_miss = None;
+    }
+
+    /// A query matched nothing, `best` being the closest candidate.
+    pub fn missed(&mut self, query: &str, best: Option<(f64, &PathBuf)>) {
+        self.last_miss = best.map(|(score, path)| Sample {
+            path: path.clone(),
+            score,
+            query: query.to_string(),
+            at: now(),
+        });
+    }
+
+    /// The query of the last jump, if the user is leaving its destination
+    /// (`cwd`) for `destination` right after getting there.
+    pub fn leaving_last_jump(&self, cwd: &Path, destination: &Path) -> Option<&str> {
+        let jump = self.last_jump.as_ref()?;
+        let recent = now().saturating_sub(jump.at) < CORRECTION_WINDOW;
+        (recent && jump.path == cwd && jump.path != destination).then_some(jump.query.as_str())
+    }
+
+    /// The last jump was corrected, returns how many times that happened for
+    /// its query and destination.
+    pub fn corrected(&mut self) -> Option<(String, PathBuf, u32)> {
+        let jump = self.last_jump.take()?;
+        let b = bucket(jump.score);
+        self.accepted[b] = self.accepted[b].saturating_sub(1);
+        self.rejected[b] += 1;
+
+        let count = match self
+            .corrections
+            .iter_mut()
+            .find(|c| c.query == jump.query && c.path == jump.path)
+        {
+            Some(correction) => {
+                correction.count += 1;
+                correction.count
+            }
+            None => {
+                self.corrections.push(Correction {
+                    query: jump.query.clone(),
+                    path: jump.path.clone(),
+                    count: 1,
+                });
+                1
+            }
+        };
+        Some((jump.query, jump.path, count))
+    }
+
+    /// The user went to a directory by its path.
+    pub fn visited(&mut self, path: &Path) {
+        if let Some(miss) = self.last_miss.take() {
+            if miss.path == path {
+                self.accepted[bucket(miss.score)] += 1;
+            }
+        }
+        self.last_jump = None;
+    }
+
+    /// Drop the samples and corrections about directories `private` matches.
+    /// Returns whether there were any.
+    pub fn scrub(&mut self, private: impl Fn(&Path) -> bool) -> bool {
+        let before = (
+            self.last_jump.is_some(),
+            self.last_miss.is_some(),
+            self.corrections.len(),
+        );
+        self.last_jump = self.last_jump.take().filter(|s| !private(&s.path));
+        self.last_miss = self.last_miss.take().filter(|s| !private(&s.path));
+        self.corrections.retain(|c| !private(&c.path));
+        before
+            != (
+                self.last_jump.is_some(),
+                self.last_miss.is_some(),
+                self.corrections.len(),
+            )
+    }
+
+    /// The user forgot a directory.
+    pub fn forgot(&mut self, path: &Path) {
+        if let Some(jump) = self.last_jump.take() {
+            if jump.path == path {
+                let b = bucket(jump.score);
+                self.accepted[b] = self.accepted[b].saturating_sub(1);
+                self.rejected[b] += 1;
+            }
+        }
+    }
+
+    pub fn samples(&self) -> u32 {
+        self.accepted.iter().chain(&self.rejected).sum()
+    }
+
+    /// The threshold losing the fewest accepted matches while letting the
+    /// fewest rejected ones through, preferring the one closest to `current`
+    /// on ties.
+    pub fn suggest(&self, current: f64) -> Option<f64> {
+        if self.samples() < MIN_SAMPLES {
+            return None;
+        }
+        (1..BUCKETS)
+            .map(|k| {
+                let lost: u32 = self.accepted[..k].iter().sum();
+                let let_through: u32 = self.rejected[k..].iter().sum();
+                (lost + let_through, k as f64 * BUCKET_WIDTH)
+            })
+            .min_by(|(cost1, t1), (cost2, t2)| {
+                cost1
+                    .cmp(cost2)
+                    .then((t1 - current).abs().total_cmp(&(t2 - current).abs()))
+            })
+            .map(|(_, threshold)| threshold)
+    }
+
+    pub fn print_histogram(&self) {
+        println!("score      accepted  rejected");
+        for b in 0..BUCKETS {
+            if self.accepted[b] == 0 && self.rejected[b] == 0 {
+                continue;
+            }
+            println!(
+                "{:.2}-{:.2}  {:>8}  {:>8}",
+                b as f64 * BUCKET_WIDTH,
+                (b + 1) as f64 * BUCKET_WIDTH,
+                self.accepted[b],
+                self.rejected[b]
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn corrupt_stats_fall_back_to_usable_defaults() {
+        let file = std::env::temp_dir().join(format!("wd-test-stats-{}", std::process::id()));
+        std::fs::write(&file, "garbage").unwrap();
+        let opened = Stats::open(file.clone());
+        std::fs::remove_file(&file).unwrap();
+        assert!(opened.is_err());
+
+        // what `Opts::open_stats` callers fall back to
+        let mut stats = Stats::default();
+        let path = Path::new("/tmp/project");
+        stats.jumped("project", path, 0.95);
+        assert_eq!(stats.samples(), 1);
+        assert_eq!(stats.corrected().map(|(_, _, count)| count), Some(1));
+        // forgetting the destination right away turns the jump into a rejection
+        stats.forgot(path);
+        assert!(stats.last_jump.is_none());
+        assert_eq!(stats.accepted[bucket(0.95)], 0);
+        assert_eq!(stats.rejected[bucket(0.95)], 1);
+        assert_eq!(stats.samples(), 1);
+
+        // going to the closest candidate of a miss accepts its score
+        let best = path.to_path_buf();
+        stats.missed("proj", Some((0.3, &best)));
+        stats.visited(path);
+        assert!(stats.last_miss.is_none());
+        assert_eq!(stats.accepted[bucket(0.3)], 1);
+        assert_eq!(stats.samples(), 2);
+    }
+}
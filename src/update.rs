@@ -0,0 +1,130 @@
+//! Replacing the running binary with the latest GitHub release, for installs
+//! made outside of a package manager. Releases carry one `wdbin-<arch>-<os>`
+//! binary per platform next to its `.sha256` checksum file.
+
+use crate::messages;
+use eyre::{bail, eyre, Context};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const LATEST_RELEASE: &str = "https://api.github.com/repos/obayemi/wd/releases/latest";
+
+#[derive(Deserialize)]
+pub struct Release {
+    pub tag_name: String,
+    assets: Vec<Asset>,
+}
+
+#[derive(Deserialize)]
+struct Asset {
+    name: String,
+    browser_download_url: String,
+}
+
+impl Release {
+    pub fn version(&self) -> &str {
+        self.tag_name.trim_start_matches('v')
+    }
+
+    fn asset(&self, name: &str) -> eyre::Result<&Asset> {
+        self.assets
+            .iter()
+            .find(|a| a.name == name)
+            .ok_or_else(|| eyre!(messages::no_asset(&self.tag_name, name)))
+    }
+}
+
+/// Name of the release binary for the platform wd was built for.
+fn asset_name() -> String {
+    format!("wdbin-{}-{}", std::env::consts::ARCH, std::env::consts::OS)
+}
+
+fn curl(url: &str, output: Option<&Path>) -> eyre::Result<Vec<u8>> {
+    let mut cmd = Command::new("curl");
+    cmd.args(["-fsSL", "-H", "Accept: application/vnd.github+json", url]);
+    if let Some(output) = output {
+        cmd.arg("-o").arg(output);
+    }
+    let out = cmd
+        .output()
+        .wrap_err_with(|| messages::couldnt_run("curl"))?;
+    if !out.status.success() {
+        bail!(messages::download_failed(
+            url,
+            String::from_utf8_lossy(&out.stderr).trim()
+        ));
+    }
+    Ok(out.stdout)
+}
+
+/// Hex sha256 of `path`, from whichever of `sha256sum` or `shasum` exists.
+fn sha256(path: &Path) -> eyre::Result<String> {
+    let attempts: [(&str, &[&str]); 2] = [("sha256sum", &[]), ("shasum", &["-a", "256"])];
+    for (program, args) in attempts {
+        let Ok(out) = Command::new(program).args(args).arg(path).output() else {
+            continue;
+        };
+        if out.status.success() {
+            return String::from_utf8_lossy(&out.stdout)
+                .split_whitespace()
+                .next()
+                .map(str::to_lowercase)
+                .ok_or_else(|| eyre!(messages::empty_checksum(&path.display().to_string())));
+        }
+    }
+    bail!(messages::no_checksum_tool())
+}
+
+/// `1.10.0` is newer than `1.9.3`, comparing numbers instead of strings.
+pub fn is_newer(version: &str, current: &str) -> bool {
+    let parse = |v: &str| -> Vec<u64> { v.split('.').map(|n| n.parse().unwrap_or(0)).collect() };
+    parse(version) > parse(current)
+}
+
+pub fn latest() -> eyre::Result<Release> {
+    let body = curl(LATEST_RELEASE, None)?;
+    serde_json::from_slice(&body).wrap_err_with(messages::bad_release_list)
+}
+
+/// Download this platform's binary from `release`, compare it to the sha256
+/// published next to it and move it over the running executable. The sha256
+/// comes from the same release, so this catches broken downloads, not a
+/// tampered release. The rename is atomic, a failure leaves the current
+/// binary untouched.
+pub fn install(release: &Release) -> eyre::Result<PathBuf> {
+    let name = asset_name();
+    let binary = release.asset(&name)?;
+    let checksum = release.asset(&format!("{name}.sha256"))?;
+
+    let exe = std::env::current_exe()
+        .and_then(|exe| exe.canonicalize())
+        .wrap_err_with(messages::no_exe)?;
+    // next to the executable, renaming across filesystems isn't atomic
+    let mut tmp = exe.clone().into_os_string();
+    tmp.push(".update");
+    let tmp = PathBuf::from(tmp);
+
+    let res = (|| {
+        curl(&binary.browser_download_url, Some(&tmp))?;
+        let expected = String::from_utf8_lossy(&curl(&checksum.browser_download_url, None)?)
+            .split_whitespace()
+            .next()
+            .map(str::to_lowercase)
+            .ok_or_else(|| eyre!(messages::empty_checksum(&checksum.name)))?;
+        let actual = sha256(&tmp)?;
+        if actual != expected {
+            bail!(messages::checksum_mismatch(&name, &expected, &actual));
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&tmp, std::fs::Permissions::from_mode(0o755))?;
+        }
+        std::fs::rename(&tmp, &exe).wrap_err_with(|| messages::couldnt_replace(&exe))
+    })();
+    if res.is_err() {
+        std::fs::remove_file(&tmp).ok();
+    }
+    res.map(|()| exe)
+}
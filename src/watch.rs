@@ -0,0 +1,61 @@
+//! Following the filesystem so entries don't outlive their directories:
+//! `wdbin watch` watches the parent of every entry and forgets directories
+//! when they're deleted, or moves their history when they're renamed.
+
+use crate::store::Entry;
+use notify::event::{ModifyKind, RenameMode};
+use notify::{Event, EventKind};
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+
+/// How often the watched directories are refreshed from the db, to pick up
+/// entries added in the meantime.
+pub const REFRESH_SECS: u64 = 60;
+
+/// How long a directory moved away waits for the other end of the rename
+/// before being considered deleted.
+pub const RENAME_WAIT_MS: u64 = 500;
+
+#[derive(Debug)]
+pub enum Change {
+    Removed(PathBuf),
+    Renamed {
+        from: PathBuf,
+        to: PathBuf,
+    },
+    /// first half of a rename, the second may follow if it stays in a
+    /// watched directory
+    MovedAway(PathBuf),
+}
+
+/// Existing parents of the entries, the directories to watch.
+pub fn parents(entries: &[Entry]) -> BTreeSet<PathBuf> {
+    entries
+        .iter()
+        .filter_map(|e| e.path.parent())
+        .filter(|parent| parent.is_dir())
+        .map(PathBuf::from)
+        .collect()
+}
+
+/// What `event` did to a directory wd may know about. Renames the backend
+/// can't pair are removals if the path is gone, and ignored otherwise.
+pub fn change(event: &Event) -> Option<Change> {
+    match (&event.kind, event.paths.as_slice()) {
+        (EventKind::Remove(_), [path, ..]) => Some(Change::Removed(path.clone())),
+        (EventKind::Modify(ModifyKind::Name(RenameMode::Both)), [from, to, ..]) => {
+            Some(Change::Renamed {
+                from: from.clone(),
+                to: to.clone(),
+            })
+        }
+        (EventKind::Modify(ModifyKind::Name(RenameMode::From)), [path, ..]) => {
+            Some(Change::MovedAway(path.clone()))
+        }
+        (EventKind::Modify(ModifyKind::Name(RenameMode::To)), _) => None,
+        (EventKind::Modify(ModifyKind::Name(_)), [path, ..]) if !path.exists() => {
+            Some(Change::Removed(path.clone()))
+        }
+        _ => None,
+    }
+}